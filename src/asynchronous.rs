@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use crate::defs::{FileError, FileOptions, Operation};
+
+/// A file opened with `FileOptions::open_async`, whose I/O runs without
+/// blocking the async runtime.
+///
+/// Besides the methods mirroring `File`, it implements tokio's `AsyncRead`,
+/// `AsyncWrite` and `AsyncSeek`, so it works with `tokio::io::copy` and friends.
+pub struct AsyncFile {
+    file_name: PathBuf,
+    file_options: FileOptions,
+    inner: tokio::fs::File,
+}
+
+impl AsyncFile {
+    pub fn file_name(&self) -> &Path {
+        &self.file_name
+    }
+
+    pub fn file_options(&self) -> FileOptions {
+        self.file_options
+    }
+
+    /// Read the rest of the file as a string, like `Reader::fread`.
+    pub async fn read(&mut self) -> Result<String, FileError> {
+        self.require(FileOptions::Read, Operation::Read)?;
+        let mut buf = String::new();
+        match self.inner.read_to_string(&mut buf).await {
+            Ok(_) => Ok(buf),
+            Err(e) => Err(self.error(Operation::Read, e)),
+        }
+    }
+
+    /// Read the rest of the file as raw bytes, like `Reader::fread_bytes`.
+    pub async fn read_bytes(&mut self) -> Result<Vec<u8>, FileError> {
+        self.require(FileOptions::Read, Operation::Read)?;
+        let mut buf = Vec::new();
+        match self.inner.read_to_end(&mut buf).await {
+            Ok(_) => Ok(buf),
+            Err(e) => Err(self.error(Operation::Read, e)),
+        }
+    }
+
+    /// Write all of `buf`, like `Writer::fwrite`, returning its length.
+    ///
+    /// The data has reached the OS once this returns, as with the sync API.
+    pub async fn write(&mut self, buf: &str) -> Result<usize, FileError> {
+        self.write_bytes(buf.as_bytes()).await
+    }
+
+    /// Write all of `buf` as raw bytes, like `Writer::fwrite_bytes`, returning its length.
+    pub async fn write_bytes(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        self.require(FileOptions::Write, Operation::Write)?;
+        let result = match self.inner.write_all(buf).await {
+            Ok(()) => self.inner.flush().await,
+            Err(e) => Err(e),
+        };
+        result
+            .map(|()| buf.len())
+            .map_err(|e| self.error(Operation::Write, e))
+    }
+
+    /// Wait until everything written has reached the OS, like `Writer::fflush`.
+    pub async fn flush(&mut self) -> Result<(), FileError> {
+        match self.inner.flush().await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(self.error(Operation::Flush, e)),
+        }
+    }
+
+    /// Seek to `pos`, returning the new offset from the start of the file, like `File::seek_from`.
+    pub async fn seek(&mut self, pos: std::io::SeekFrom) -> Result<u64, FileError> {
+        match self.inner.seek(pos).await {
+            Ok(position) => Ok(position),
+            Err(e) => Err(self.error(Operation::Seek, e)),
+        }
+    }
+
+    // fail early with a clear message if the file wasn't opened with `option`, as `File` does
+    fn require(&self, option: FileOptions, operation: Operation) -> Result<(), FileError> {
+        if self.file_options.contains(option) {
+            return Ok(());
+        }
+
+        let message = format!("file was not opened with {}", option);
+        Err(self.error(
+            operation,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message),
+        ))
+    }
+
+    fn error(&self, operation: Operation, error: std::io::Error) -> FileError {
+        FileError::with_file(operation, error, &self.file_name, self.file_options)
+    }
+}
+
+impl AsyncRead for AsyncFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AsyncFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for AsyncFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: std::io::SeekFrom) -> std::io::Result<()> {
+        Pin::new(&mut self.inner).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_complete(cx)
+    }
+}
+
+impl FileOptions {
+    /// Open a file for use from async code, with the same options and checks as `open`.
+    pub async fn open_async<P>(self, file_name: P) -> Result<AsyncFile, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let file_name = file_name.as_ref();
+        self.validate_for(file_name)?;
+
+        if self.contains(FileOptions::CreateDirs) {
+            if let Some(parent) = file_name.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| FileError::with_file(Operation::Open, e, file_name, self))?;
+            }
+        }
+
+        match tokio::fs::OpenOptions::from(self.std_options())
+            .open(file_name)
+            .await
+        {
+            Ok(inner) => Ok(AsyncFile {
+                file_name: file_name.to_path_buf(),
+                file_options: self,
+                inner,
+            }),
+            Err(e) => Err(FileError::with_file(Operation::Open, e, file_name, self)),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+/// A file that is written to a temporary sibling and only renamed over its
+/// destination on `commit`, so the destination is never left half-written.
+///
+/// The temporary file takes on the destination's permissions, if it already
+/// exists. Dropping an `AtomicFile` without committing removes the temporary
+/// file and leaves the destination untouched.
+pub struct AtomicFile {
+    file: File,
+    temp_path: PathBuf,
+    target_path: PathBuf,
+    committed: bool,
+}
+
+impl AtomicFile {
+    /// Start an atomic write to `file_name`, creating the temporary file in the same directory.
+    pub fn create<P>(file_name: P) -> Result<AtomicFile, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let target_path = file_name.as_ref().to_path_buf();
+        let name = target_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let options = FileOptions::ExclusiveCreate | FileOptions::Write;
+        let file = File::create_unique(&parent_dir(&target_path), &format!(".{}", name), options)?;
+        let mut atomic = AtomicFile {
+            temp_path: file.file_name().to_path_buf(),
+            file,
+            target_path,
+            committed: false,
+        };
+
+        // dropping `atomic` on failure removes the temporary file
+        if let Ok(metadata) = std::fs::metadata(&atomic.target_path) {
+            atomic.file.set_permissions(metadata.permissions())?;
+        }
+        Ok(atomic)
+    }
+
+    /// Sync the written data to disk and rename it over the destination.
+    ///
+    /// On Unix the directory is synced too, so the rename itself survives a crash.
+    pub fn commit(mut self) -> Result<(), FileError> {
+        self.file
+            .flush()
+            .map_err(|e| self.file.error(Operation::Flush, e))?;
+        self.file
+            .underlying_file
+            .sync_all()
+            .map_err(|e| self.file.error(Operation::Sync, e))?;
+        std::fs::rename(&self.temp_path, &self.target_path)
+            .map_err(|e| self.file.error(Operation::Rename, e))?;
+        self.committed = true;
+
+        #[cfg(unix)]
+        {
+            let dir = parent_dir(&self.target_path);
+            std::fs::File::open(&dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(|e| FileError::with_file(Operation::Sync, e, &dir, FileOptions::Read))?;
+        }
+        Ok(())
+    }
+}
+
+// the directory `path` is in, as something that can be joined onto or opened
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.file.write_all(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+impl File {
+    /// Replace the contents of `file_name` with `data` atomically.
+    ///
+    /// Either the old contents or the new contents will be on disk, never a mix.
+    pub fn write_atomic<P>(file_name: P, data: &[u8]) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = AtomicFile::create(file_name)?;
+        file.write_all(data)
+            .map_err(|e| file.file.error(Operation::Write, e))?;
+        file.commit()
+    }
+}
@@ -0,0 +1,71 @@
+use std::io::{Read, Write};
+
+use crate::defs::{FileError, Operation};
+
+// define a reader method for each integer type and byte order
+macro_rules! read_int {
+    ($($name:ident: $int:ty, $from_bytes:ident;)*) => {
+        $(
+            fn $name(&mut self) -> Result<$int, FileError> {
+                let mut buf = [0; std::mem::size_of::<$int>()];
+                self.read_exact(&mut buf)
+                    .map_err(|e| FileError::during(Operation::Read, e))?;
+                Ok(<$int>::$from_bytes(buf))
+            }
+        )*
+    };
+}
+
+// define a writer method for each integer type and byte order
+macro_rules! write_int {
+    ($($name:ident: $int:ty, $to_bytes:ident;)*) => {
+        $(
+            fn $name(&mut self, value: $int) -> Result<(), FileError> {
+                self.write_all(&value.$to_bytes())
+                    .map_err(|e| FileError::during(Operation::Write, e))
+            }
+        )*
+    };
+}
+
+/// Reading fixed-size integers in an explicit byte order, advancing the position.
+///
+/// Short reads fail with `std::io::ErrorKind::UnexpectedEof`.
+pub trait BinaryReader: Read {
+    read_int! {
+        read_u16_le: u16, from_le_bytes;
+        read_u16_be: u16, from_be_bytes;
+        read_u32_le: u32, from_le_bytes;
+        read_u32_be: u32, from_be_bytes;
+        read_u64_le: u64, from_le_bytes;
+        read_u64_be: u64, from_be_bytes;
+        read_i16_le: i16, from_le_bytes;
+        read_i16_be: i16, from_be_bytes;
+        read_i32_le: i32, from_le_bytes;
+        read_i32_be: i32, from_be_bytes;
+        read_i64_le: i64, from_le_bytes;
+        read_i64_be: i64, from_be_bytes;
+    }
+}
+
+/// Writing fixed-size integers in an explicit byte order, advancing the position.
+pub trait BinaryWriter: Write {
+    write_int! {
+        write_u16_le: u16, to_le_bytes;
+        write_u16_be: u16, to_be_bytes;
+        write_u32_le: u32, to_le_bytes;
+        write_u32_be: u32, to_be_bytes;
+        write_u64_le: u64, to_le_bytes;
+        write_u64_be: u64, to_be_bytes;
+        write_i16_le: i16, to_le_bytes;
+        write_i16_be: i16, to_be_bytes;
+        write_i32_le: i32, to_le_bytes;
+        write_i32_be: i32, to_be_bytes;
+        write_i64_le: i64, to_le_bytes;
+        write_i64_be: i64, to_be_bytes;
+    }
+}
+
+impl<T> BinaryReader for T where T: Read + ?Sized {}
+
+impl<T> BinaryWriter for T where T: Write + ?Sized {}
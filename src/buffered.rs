@@ -0,0 +1,161 @@
+use std::io::{BufRead, BufReader, Read, Seek, Write};
+use std::path::Path;
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// A `File` with read and write buffers, for workloads made of many small reads
+/// or writes that would otherwise each cost a system call.
+///
+/// Buffered writes reach the file before any read, on `flush`, on `into_inner`
+/// and when the `BufferedFile` is dropped. Errors while flushing on drop are
+/// ignored, so call `flush` to observe them.
+pub struct BufferedFile {
+    // only `None` once `into_inner` has taken the file back
+    reader: Option<BufReader<File>>,
+    writes: Vec<u8>,
+    capacity: usize,
+}
+
+impl BufferedFile {
+    fn new(file: File, capacity: usize) -> BufferedFile {
+        BufferedFile {
+            reader: Some(BufReader::with_capacity(capacity, file)),
+            writes: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Read the next line with its `\n` or `\r\n` ending stripped, or `None` at the end of the file.
+    pub fn read_line(&mut self) -> Result<Option<String>, FileError> {
+        self.flush_writes()
+            .map_err(|e| self.file().error(Operation::Write, e))?;
+
+        let mut line = String::new();
+        let read = self.reader().read_line(&mut line);
+        if read.map_err(|e| self.file().error(Operation::Read, e))? == 0 {
+            return Ok(None);
+        }
+
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Iterate over the remaining lines, with line endings stripped.
+    pub fn lines(&mut self) -> impl Iterator<Item = Result<String, FileError>> + '_ {
+        let flush_error = self
+            .flush_writes()
+            .err()
+            .map(|e| self.file().error(Operation::Write, e));
+        let file_name = self.file().file_name().to_path_buf();
+        let file_options = self.file().file_options();
+
+        flush_error
+            .map(Err)
+            .into_iter()
+            .chain(self.reader().lines().map(move |line| {
+                line.map_err(|e| FileError::with_file(Operation::Read, e, &file_name, file_options))
+            }))
+    }
+
+    /// Flush any buffered writes and hand back the plain `File`, positioned
+    /// just after the last byte read or written through the buffer.
+    pub fn into_inner(mut self) -> Result<File, FileError> {
+        self.flush_writes()
+            .map_err(|e| self.file().error(Operation::Write, e))?;
+        self.discard_reads()
+            .map_err(|e| self.file().error(Operation::Seek, e))?;
+        Ok(self.reader.take().unwrap().into_inner())
+    }
+
+    fn reader(&mut self) -> &mut BufReader<File> {
+        self.reader.as_mut().unwrap()
+    }
+
+    fn file(&self) -> &File {
+        self.reader.as_ref().unwrap().get_ref()
+    }
+
+    fn flush_writes(&mut self) -> std::io::Result<()> {
+        if let Some(reader) = self.reader.as_mut() {
+            if !self.writes.is_empty() {
+                reader.get_mut().write_all(&self.writes)?;
+                self.writes.clear();
+            }
+        }
+        Ok(())
+    }
+
+    // drop any read-ahead so the file position matches what has been consumed
+    fn discard_reads(&mut self) -> std::io::Result<()> {
+        let reader = self.reader();
+        if !reader.buffer().is_empty() {
+            let position = reader.stream_position()?;
+            reader.seek(std::io::SeekFrom::Start(position))?;
+        }
+        Ok(())
+    }
+}
+
+impl Read for BufferedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.flush_writes()?;
+        self.reader().read(buf)
+    }
+}
+
+impl Write for BufferedFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // refuse up front, as otherwise the error would only surface on a flush
+        self.file().check(FileOptions::Write)?;
+        self.discard_reads()?;
+        if self.writes.len() + buf.len() > self.capacity {
+            self.flush_writes()?;
+        }
+
+        if buf.len() >= self.capacity {
+            return self.reader().get_mut().write(buf);
+        }
+        self.writes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flush_writes()?;
+        self.reader().get_mut().flush()
+    }
+}
+
+impl Drop for BufferedFile {
+    fn drop(&mut self) {
+        let _ = self.flush_writes();
+    }
+}
+
+impl File {
+    /// Wrap the file in read and write buffers of the default size.
+    pub fn buffered(self) -> BufferedFile {
+        BufferedFile::new(self, DEFAULT_CAPACITY)
+    }
+
+    /// Wrap the file in read and write buffers of `capacity` bytes each.
+    pub fn buffered_with_capacity(self, capacity: usize) -> BufferedFile {
+        BufferedFile::new(self, capacity)
+    }
+}
+
+impl FileOptions {
+    /// Open a file and wrap it in read and write buffers of the default size.
+    pub fn open_buffered<P>(self, file_name: P) -> Result<BufferedFile, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self.open(file_name)?.buffered())
+    }
+}
@@ -0,0 +1,65 @@
+use std::io::{Read, Seek};
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl File {
+    /// The CRC-32 (IEEE) checksum of the whole file.
+    ///
+    /// The file is read from the start in fixed-size chunks rather than all at
+    /// once, and the position is put back where it was afterwards.
+    #[cfg(feature = "crc32")]
+    #[doc(alias = "checksum_crc32")]
+    pub fn crc32(&mut self) -> Result<u32, FileError> {
+        let mut hasher = crc32fast::Hasher::new();
+        self.for_each_chunk(|chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
+
+    /// The SHA-256 digest of the whole file.
+    ///
+    /// The file is read from the start in fixed-size chunks rather than all at
+    /// once, and the position is put back where it was afterwards.
+    #[cfg(feature = "sha2")]
+    #[doc(alias = "checksum_sha256")]
+    pub fn sha256(&mut self) -> Result<[u8; 32], FileError> {
+        use sha2::Digest;
+
+        let mut hasher = sha2::Sha256::new();
+        self.for_each_chunk(|chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize().into())
+    }
+
+    /// The SHA-256 digest of the whole file as a lowercase hex string.
+    #[cfg(feature = "sha2")]
+    pub fn sha256_hex(&mut self) -> Result<String, FileError> {
+        Ok(self
+            .sha256()?
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect())
+    }
+
+    // feed the whole file to `f` a chunk at a time, then restore the position, even on failure
+    fn for_each_chunk<F>(&mut self, mut f: F) -> Result<(), FileError>
+    where
+        F: FnMut(&[u8]),
+    {
+        self.require(FileOptions::Read)?;
+        self.keeping_position(|file| {
+            let mut buf = vec![0; CHUNK_SIZE];
+            file.underlying_file
+                .rewind()
+                .map_err(|e| file.error(Operation::Seek, e))?;
+            loop {
+                match file.underlying_file.read(&mut buf) {
+                    Ok(0) => return Ok(()),
+                    Ok(n) => f(&buf[..n]),
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(file.error(Operation::Read, e)),
+                }
+            }
+        })
+    }
+}
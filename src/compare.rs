@@ -0,0 +1,54 @@
+use std::io::{Read, Seek};
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl File {
+    /// Whether this file and `other` have the same contents.
+    ///
+    /// The lengths are compared first, then both files are read from the start
+    /// in chunks, stopping at the first difference, so neither has to fit in
+    /// memory. Both positions are put back where they were afterwards.
+    pub fn contents_equal(&mut self, other: &mut File) -> Result<bool, FileError> {
+        self.require(FileOptions::Read)?;
+        other.require(FileOptions::Read)?;
+
+        let len = self.len()?;
+        if len != other.len()? {
+            return Ok(false);
+        }
+
+        self.keeping_position(|this| {
+            other.keeping_position(|other| {
+                this.underlying_file
+                    .rewind()
+                    .map_err(|e| this.error(Operation::Seek, e))?;
+                other
+                    .underlying_file
+                    .rewind()
+                    .map_err(|e| other.error(Operation::Seek, e))?;
+
+                let mut ours = vec![0; CHUNK_SIZE];
+                let mut theirs = vec![0; CHUNK_SIZE];
+                let mut remaining = len;
+                while remaining > 0 {
+                    let size = remaining.min(CHUNK_SIZE as u64) as usize;
+                    this.underlying_file
+                        .read_exact(&mut ours[..size])
+                        .map_err(|e| this.error(Operation::Read, e))?;
+                    other
+                        .underlying_file
+                        .read_exact(&mut theirs[..size])
+                        .map_err(|e| other.error(Operation::Read, e))?;
+
+                    if ours[..size] != theirs[..size] {
+                        return Ok(false);
+                    }
+                    remaining -= size as u64;
+                }
+                Ok(true)
+            })
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+impl File {
+    /// Iterate over the rows of a CSV file from the current position, each as
+    /// a vector of fields.
+    ///
+    /// Quoted fields may contain commas, quotes and newlines. With
+    /// `has_headers`, the first row is taken as a header and skipped; without
+    /// it, the first row is data like the rest. Every row must have as many
+    /// fields as the first. As with `lines`, the rows are read through a buffer,
+    /// so the position afterwards may be past the last row returned.
+    pub fn csv_records(
+        &mut self,
+        has_headers: bool,
+    ) -> impl Iterator<Item = Result<Vec<String>, FileError>> + '_ {
+        let file_name = self.file_name().to_path_buf();
+        let file_options = self.file_options();
+
+        // a file that can't be read yields that one error and nothing else
+        let (not_readable, records) = match self.require(FileOptions::Read) {
+            Ok(()) => {
+                let reader = ::csv::ReaderBuilder::new()
+                    .has_headers(has_headers)
+                    .from_reader(&mut self.underlying_file);
+                (None, Some(reader.into_records()))
+            }
+            Err(e) => (Some(e), None),
+        };
+        not_readable
+            .map(Err)
+            .into_iter()
+            .chain(
+                records
+                    .into_iter()
+                    .flatten()
+                    .map(move |record| match record {
+                        Ok(record) => Ok(record.iter().map(String::from).collect()),
+                        Err(e) => Err(FileError::with_file(
+                            Operation::Read,
+                            e.into(),
+                            &file_name,
+                            file_options,
+                        )),
+                    }),
+            )
+    }
+}
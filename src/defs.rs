@@ -1,21 +1,201 @@
-use std::io::{Read, Seek, Write};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Read, Seek, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
 
 use bitflags::bitflags;
 
+// how far `read_until` reads ahead, at first and at most
+const MIN_READ_AHEAD: usize = 256;
+const MAX_READ_AHEAD: usize = 8 * 1024;
+
+// emit a debug-level diagnostic through the `log` crate when the `logging` feature is on
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "logging")]
+        log::debug!($($arg)*);
+        // keep the arguments used, and checked, when it's off
+        #[cfg(not(feature = "logging"))]
+        let _ = format_args!($($arg)*);
+    };
+}
+
 bitflags! {
-    #[derive(Debug, Clone, Copy)]
-    pub struct FileOptions: u32 {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Flags: u32 {
         const Read = 0b00000001;
         const Write = 0b00000010;
         const Create = 0b00000100;
         const ExclusiveCreate = 0b00001000;
         const Truncate = 0b00010000;
         const Append = 0b00100000;
+        const CreateDirs = 0b01000000;
+        const SyncOnClose = 0b100000000;
+        const SyncDataOnClose = 0b1000000000;
 
         const Uninitialized = 0b10000000;
     }
 }
 
+/// Options for opening a file: a set of flags combined with `|` or the builder
+/// methods, plus settings that aren't simple flags, like the Unix permission mode.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FileOptions {
+    flags: Flags,
+    mode: Option<u32>,
+    durability: Option<Durability>,
+    line_ending: Option<LineEnding>,
+}
+
+/// How far `flush` goes to make written data survive a crash, set with
+/// `FileOptions::durability`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Hand the data to the OS, which writes it to disk in its own time. Fast,
+    /// but recent writes can be lost if the machine crashes or loses power.
+    #[default]
+    None,
+    /// Also wait until the data, and any metadata needed to read it back like
+    /// the file's length, is on disk, as `File::sync_data` does. Cheaper than
+    /// `All` because timestamps and the like are left to be written later.
+    Data,
+    /// Also wait until the data and all the file's metadata are on disk, as
+    /// `File::sync_all` does.
+    All,
+}
+
+#[allow(non_upper_case_globals)]
+impl FileOptions {
+    pub const Read: FileOptions = FileOptions::from_flags(Flags::Read);
+    pub const Write: FileOptions = FileOptions::from_flags(Flags::Write);
+    pub const Create: FileOptions = FileOptions::from_flags(Flags::Create);
+    pub const ExclusiveCreate: FileOptions = FileOptions::from_flags(Flags::ExclusiveCreate);
+    pub const Truncate: FileOptions = FileOptions::from_flags(Flags::Truncate);
+    pub const Append: FileOptions = FileOptions::from_flags(Flags::Append);
+    /// Create any missing parent directories before creating the file.
+    pub const CreateDirs: FileOptions = FileOptions::from_flags(Flags::CreateDirs);
+    /// Sync written data to disk when the file is closed or dropped.
+    pub const SyncOnClose: FileOptions = FileOptions::from_flags(Flags::SyncOnClose);
+    /// Sync written data to disk with the cheaper `sync_data` when the file is
+    /// closed or dropped, which is plenty for append-only logs. `SyncOnClose`
+    /// wins if both are set.
+    pub const SyncDataOnClose: FileOptions = FileOptions::from_flags(Flags::SyncDataOnClose);
+
+    pub const Uninitialized: FileOptions = FileOptions::from_flags(Flags::Uninitialized);
+
+    const fn from_flags(flags: Flags) -> FileOptions {
+        FileOptions {
+            flags,
+            mode: None,
+            durability: None,
+            line_ending: None,
+        }
+    }
+
+    /// Whether all the flags in `other` are set.
+    pub fn contains(&self, other: FileOptions) -> bool {
+        self.flags.contains(other.flags)
+    }
+}
+
+impl std::ops::BitOr for FileOptions {
+    type Output = FileOptions;
+
+    fn bitor(self, rhs: FileOptions) -> FileOptions {
+        FileOptions {
+            flags: self.flags | rhs.flags,
+            mode: rhs.mode.or(self.mode),
+            durability: rhs.durability.or(self.durability),
+            line_ending: rhs.line_ending.or(self.line_ending),
+        }
+    }
+}
+
+impl std::ops::BitOrAssign for FileOptions {
+    fn bitor_assign(&mut self, rhs: FileOptions) {
+        *self = *self | rhs;
+    }
+}
+
+impl std::ops::Sub for FileOptions {
+    type Output = FileOptions;
+
+    fn sub(self, rhs: FileOptions) -> FileOptions {
+        FileOptions {
+            flags: self.flags - rhs.flags,
+            mode: self.mode,
+            durability: self.durability,
+            line_ending: self.line_ending,
+        }
+    }
+}
+
+/// Formats the set flags as e.g. `Read|Write|Create`.
+impl std::fmt::Display for FileOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (name, _)) in self.flags.iter_names().enumerate() {
+            if i > 0 {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the mode strings `fopen` accepts: `r`, `w`, `a`, `r+`, `w+` and `a+`,
+/// optionally with a `b`, which is ignored.
+impl std::str::FromStr for FileOptions {
+    type Err = FileError;
+
+    fn from_str(mode: &str) -> Result<FileOptions, FileError> {
+        let options = match mode.replace('b', "").as_str() {
+            "r" => FileOptions::Read,
+            "w" => FileOptions::Write | FileOptions::Create | FileOptions::Truncate,
+            "a" => FileOptions::Write | FileOptions::Append | FileOptions::Create,
+            "r+" => FileOptions::Read | FileOptions::Write,
+            "w+" => {
+                FileOptions::Read | FileOptions::Write | FileOptions::Create | FileOptions::Truncate
+            }
+            "a+" => {
+                FileOptions::Read | FileOptions::Write | FileOptions::Append | FileOptions::Create
+            }
+            _ => {
+                let message = format!("unknown mode string '{}'", mode);
+                return Err(FileError {
+                    underlying_error: std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        message.clone(),
+                    ),
+                    message,
+                    file_name: PathBuf::new(),
+                    file_options: FileOptions::Uninitialized,
+                    operation: Operation::Open,
+                });
+            }
+        };
+        Ok(options)
+    }
+}
+
+impl std::fmt::Debug for FileOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FileOptions(")?;
+        bitflags::parser::to_writer(&self.flags, &mut *f)?;
+        if let Some(mode) = self.mode {
+            write!(f, ", mode: {:#o}", mode)?;
+        }
+        if let Some(durability) = self.durability {
+            write!(f, ", durability: {:?}", durability)?;
+        }
+        if let Some(line_ending) = self.line_ending {
+            write!(f, ", line_ending: {:?}", line_ending)?;
+        }
+        write!(f, ")")
+    }
+}
+
 impl FileOptions {
     fn is_initialized(&self) -> bool {
         !self.contains(FileOptions::Uninitialized)
@@ -69,42 +249,212 @@ impl FileOptions {
         }
     }
 
+    /// Create any missing parent directories when the file is created.
+    pub fn create_dirs(self, set: bool) -> FileOptions {
+        if set {
+            (self - FileOptions::Uninitialized) | FileOptions::CreateDirs
+        } else {
+            self
+        }
+    }
+
+    /// Sync written data to disk with `sync_all` when the file is closed or dropped.
+    pub fn sync_on_close(self, set: bool) -> FileOptions {
+        if set {
+            (self - FileOptions::Uninitialized) | FileOptions::SyncOnClose
+        } else {
+            self
+        }
+    }
+
+    /// Sync written data to disk with `sync_data` when the file is closed or dropped.
+    pub fn sync_data_on_close(self, set: bool) -> FileOptions {
+        if set {
+            (self - FileOptions::Uninitialized) | FileOptions::SyncDataOnClose
+        } else {
+            self
+        }
+    }
+
+    /// Options for reading an existing file.
+    ///
+    /// ```
+    /// let file = file::FileOptions::read_only().open("README.md").unwrap();
+    /// ```
+    pub fn read_only() -> FileOptions {
+        FileOptions::Read
+    }
+
+    /// Options for writing to an existing file without reading it.
+    ///
+    /// ```no_run
+    /// let file = file::FileOptions::write_only().open("out.txt").unwrap();
+    /// ```
+    pub fn write_only() -> FileOptions {
+        FileOptions::Write
+    }
+
+    /// Options for writing a file from scratch, creating it or truncating it as needed.
+    ///
+    /// ```no_run
+    /// let file = file::FileOptions::write_new().open("out.txt").unwrap();
+    /// ```
+    pub fn write_new() -> FileOptions {
+        FileOptions::Create | FileOptions::Write | FileOptions::Truncate
+    }
+
+    /// Options for reading and writing an existing file.
+    ///
+    /// ```no_run
+    /// let file = file::FileOptions::read_write().open("data.bin").unwrap();
+    /// ```
+    pub fn read_write() -> FileOptions {
+        FileOptions::Read | FileOptions::Write
+    }
+
+    /// Options for appending to a file, creating it if needed.
+    ///
+    /// ```no_run
+    /// let file = file::FileOptions::append_to().open("log.txt").unwrap();
+    /// ```
+    pub fn append_to() -> FileOptions {
+        FileOptions::Create | FileOptions::Write | FileOptions::Append
+    }
+
+    /// Choose what `flush` waits for on files opened for writing; see `Durability`.
+    ///
+    /// Without this, `flush` behaves as `Durability::None`.
+    pub fn durability(self, durability: Durability) -> FileOptions {
+        FileOptions {
+            durability: Some(durability),
+            ..self
+        }
+    }
+
+    /// Set the line ending the file's text APIs use, instead of `LineEnding::Lf`.
+    pub fn line_ending(self, line_ending: LineEnding) -> FileOptions {
+        FileOptions {
+            line_ending: Some(line_ending),
+            ..self
+        }
+    }
+
+    /// Set the permission bits a newly created file gets, e.g. `0o600`.
+    ///
+    /// Applied on Unix (subject to the process umask) and ignored on other platforms.
+    pub fn mode(self, mode: u32) -> FileOptions {
+        FileOptions {
+            mode: Some(mode),
+            ..self
+        }
+    }
+
     pub fn new() -> FileOptions {
         FileOptions::Uninitialized
     }
 
-    pub fn open<T>(self, file_name: T) -> Result<File, FileError>
+    /// Check that these options are initialized and don't contain conflicting flags.
+    ///
+    /// `open` runs this check itself; it's exposed so options built from user
+    /// input can be checked before a file name is known.
+    pub fn validate(&self) -> Result<(), FileError> {
+        let problem = if !self.is_initialized() {
+            "FileOptions uninitialized"
+        } else if self.contains(FileOptions::Truncate | FileOptions::Append) {
+            "Truncate and Append cannot both be set"
+        } else if self.contains(FileOptions::ExclusiveCreate | FileOptions::Truncate) {
+            "ExclusiveCreate and Truncate cannot both be set"
+        } else if self.contains(FileOptions::Truncate) && !self.contains(FileOptions::Write) {
+            "Truncate requires Write"
+        } else if self.contains(FileOptions::ExclusiveCreate) && !self.contains(FileOptions::Write)
+        {
+            "ExclusiveCreate requires Write"
+        } else if self.contains(FileOptions::Create) && !self.contains(FileOptions::Write) {
+            "Create requires Write"
+        } else if self.contains(FileOptions::Append) && !self.contains(FileOptions::Write) {
+            "Append requires Write"
+        } else if self.contains(FileOptions::CreateDirs)
+            && !self.contains(FileOptions::Create)
+            && !self.contains(FileOptions::ExclusiveCreate)
+        {
+            "CreateDirs requires Create or ExclusiveCreate"
+        } else {
+            return Ok(());
+        };
+
+        Err(FileError {
+            message: problem.to_string(),
+            file_name: PathBuf::new(),
+            file_options: *self,
+            operation: Operation::Open,
+            underlying_error: std::io::Error::new(std::io::ErrorKind::InvalidInput, problem),
+        })
+    }
+
+    pub fn open<P>(self, file_name: P) -> Result<File, FileError>
     where
-        T: ToString,
+        P: AsRef<Path>,
     {
-        if self.contains(FileOptions::Uninitialized) {
-            return Err(FileError {
-                message: "FileOptions uninitialized".to_string(),
-                underlying_error: std::io::Error::new(std::io::ErrorKind::InvalidInput, "FileOptions uninitialized"),
-            });
+        let file_name = file_name.as_ref();
+        self.validate_for(file_name)?;
+
+        if self.contains(FileOptions::CreateDirs) {
+            if let Some(parent) = file_name.parent() {
+                // a no-op when the directories already exist
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| FileError::with_file(Operation::Open, e, file_name, self))?;
+            }
+        }
+
+        match self.std_options().open(file_name) {
+            Ok(file) => {
+                debug!("opened {} with {:?}", file_name.display(), self);
+                Ok(File {
+                    file_name: file_name.to_path_buf(),
+                    file_options: self,
+                    line_ending: self.line_ending.unwrap_or_default(),
+                    delete_on_drop: false,
+                    closed: false,
+                    bytes_written: 0,
+                    underlying_file: file,
+                })
+            }
+            Err(e) => Err(FileError::with_file(Operation::Open, e, file_name, self)),
         }
+    }
+
+    // `validate`, naming the file about to be opened in the error
+    pub(crate) fn validate_for(&self, file_name: &Path) -> Result<(), FileError> {
+        self.validate().map_err(|mut e| {
+            e.file_name = file_name.to_path_buf();
+            e
+        })
+    }
 
-        let openoptions = std::fs::OpenOptions::new()
+    // the std options matching these flags and mode
+    pub(crate) fn std_options(&self) -> std::fs::OpenOptions {
+        let mut openoptions = std::fs::OpenOptions::new();
+        openoptions
             .read(self.contains(FileOptions::Read))
             .write(self.contains(FileOptions::Write))
             .create(self.contains(FileOptions::Create))
             .create_new(self.contains(FileOptions::ExclusiveCreate))
             .truncate(self.contains(FileOptions::Truncate))
-            .append(self.contains(FileOptions::Append))
-            .open(file_name.to_string());
-
-        match openoptions {
-            Ok(file) => Ok(File {
-                file_name: file_name.to_string(),
-                file_options: self,
-                underlying_file: file,
-            }),
-            Err(e) => Err(FileError {
-                message: e.to_string(),
-                underlying_error: e,
-            }),
+            .append(self.contains(FileOptions::Append));
+
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            openoptions.mode(mode);
         }
-    
+
+        openoptions
+    }
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        FileOptions::new()
     }
 }
 
@@ -120,6 +470,16 @@ impl From<u64> for SeekFrom {
     }
 }
 
+impl From<std::io::SeekFrom> for SeekFrom {
+    fn from(pos: std::io::SeekFrom) -> Self {
+        match pos {
+            std::io::SeekFrom::Start(pos) => SeekFrom::Start(pos),
+            std::io::SeekFrom::End(pos) => SeekFrom::End(pos),
+            std::io::SeekFrom::Current(pos) => SeekFrom::Current(pos),
+        }
+    }
+}
+
 impl From<SeekFrom> for std::io::SeekFrom {
     fn from(pos: SeekFrom) -> Self {
         match pos {
@@ -130,37 +490,1313 @@ impl From<SeekFrom> for std::io::SeekFrom {
     }
 }
 
+/// The line ending written by `File::writeln`, `File::write_lines` and
+/// `File::write_text`, and whether `File::read_text` normalizes line endings.
+///
+/// Only the text APIs use it; bytes written or read any other way, including
+/// lone `\r`s in them, pass through untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    /// `\r\n` on Windows and `\n` everywhere else.
+    Native,
+    /// Leave line endings in text as they are, and end lines from `writeln` with `\n`.
+    Preserve,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf | LineEnding::Preserve => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Native if cfg!(windows) => "\r\n",
+            LineEnding::Native => "\n",
+        }
+    }
+
+    /// Rewrite every `\n` or `\r\n` in `text` as this line ending, leaving lone
+    /// `\r`s alone. `Preserve` returns `text` unchanged.
+    pub fn convert(&self, text: &str) -> String {
+        if *self == LineEnding::Preserve {
+            return text.to_string();
+        }
+
+        let normalized = text.replace("\r\n", "\n");
+        match self.as_str() {
+            "\n" => normalized,
+            line_ending => normalized.replace('\n', line_ending),
+        }
+    }
+}
+
 pub struct File {
-    file_name: String,
+    file_name: PathBuf,
     file_options: FileOptions,
+    line_ending: LineEnding,
+    // set for files from `temp` until they are `persist`ed
+    delete_on_drop: bool,
+    // set by `close`, so dropping doesn't flush and sync all over again
+    closed: bool,
+    // bytes written through `Write`, for `bytes_written`
+    bytes_written: u64,
     pub underlying_file: std::fs::File,
 }
 
+/// Shows the name, options and line ending, but not the contents.
+impl std::fmt::Debug for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("File")
+            .field("file_name", &self.file_name)
+            .field("file_options", &self.file_options)
+            .field("line_ending", &self.line_ending)
+            .finish_non_exhaustive()
+    }
+}
+
+impl File {
+    /// Whether anything exists at `path`.
+    pub fn exists<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        path.as_ref().exists()
+    }
+
+    /// Whether `path` exists and is a regular file.
+    pub fn is_file<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        path.as_ref().is_file()
+    }
+
+    /// Whether `path` exists and is a directory.
+    pub fn is_dir<P>(path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        path.as_ref().is_dir()
+    }
+
+    /// Read a whole file into a string in one call.
+    ///
+    /// ```
+    /// let readme = file::File::read_to_string("README.md").unwrap();
+    /// ```
+    pub fn read_to_string<P>(path: P) -> Result<String, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = FileOptions::Read.open(path)?;
+        let mut contents = String::new();
+        match file.underlying_file.read_to_string(&mut contents) {
+            Ok(_) => Ok(contents),
+            Err(e) => Err(file.error(Operation::Read, e)),
+        }
+    }
+
+    /// Read a whole file into a byte vector in one call.
+    ///
+    /// ```
+    /// let bytes = file::File::read_to_bytes("README.md").unwrap();
+    /// ```
+    pub fn read_to_bytes<P>(path: P) -> Result<Vec<u8>, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = FileOptions::Read.open(path)?;
+        let mut contents = Vec::new();
+        match file.underlying_file.read_to_end(&mut contents) {
+            Ok(_) => Ok(contents),
+            Err(e) => Err(file.error(Operation::Read, e)),
+        }
+    }
+
+    /// Replace the contents of a file with `contents`, creating it if needed.
+    ///
+    /// ```no_run
+    /// file::File::write_string("out.txt", "hello").unwrap();
+    /// ```
+    pub fn write_string<P>(path: P, contents: &str) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = FileOptions::write_new().open(path)?;
+        file.write_all(contents.as_bytes())
+            .map_err(|e| file.error(Operation::Write, e))
+    }
+
+    /// Add `contents` to the end of a file, creating it if needed.
+    ///
+    /// ```no_run
+    /// file::File::append_string("log.txt", "started\n").unwrap();
+    /// ```
+    pub fn append_string<P>(path: P, contents: &str) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = FileOptions::append_to().open(path)?;
+        file.write_all(contents.as_bytes())
+            .map_err(|e| file.error(Operation::Write, e))
+    }
+
+    /// The name the file was opened with.
+    pub fn file_name(&self) -> &Path {
+        &self.file_name
+    }
+
+    /// The options the file was opened with.
+    pub fn file_options(&self) -> FileOptions {
+        self.file_options
+    }
+
+    /// The name the file was opened with, like `file_name`.
+    pub fn path(&self) -> &Path {
+        &self.file_name
+    }
+
+    /// The options the file was opened with, like `file_options`.
+    pub fn options(&self) -> FileOptions {
+        self.file_options
+    }
+
+    /// Read the whole file and split it into lines, with `\n` and `\r\n` endings stripped.
+    pub fn read_lines(&mut self) -> Result<Vec<String>, FileError> {
+        let contents = self.fread()?;
+        Ok(contents.lines().map(String::from).collect())
+    }
+
+    /// Read the next line with its `\n` or `\r\n` ending stripped, or `None` at the end of the file.
+    ///
+    /// Unlike `lines`, this leaves the file position just past the line ending,
+    /// so it can be interleaved with other reads. The price is a read and a
+    /// seek per line, as described for `read_until`, so prefer `lines` or
+    /// `BufferedFile::read_line` for reading a whole file line by line.
+    pub fn read_line(&mut self) -> Result<Option<String>, FileError> {
+        let mut line = match self.read_until(b'\n')? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        String::from_utf8(line).map(Some).map_err(|e| {
+            self.error(
+                Operation::Read,
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )
+        })
+    }
+
+    /// Read the next record ending in `delim`, without the delimiter, or `None` at the end of the file.
+    ///
+    /// A last record with no delimiter after it is still returned. The position
+    /// is left just past the delimiter, so this can be interleaved with other
+    /// reads. That means there is no buffer kept between calls: each call
+    /// reads ahead, starting small and growing for long records, and seeks back
+    /// over what it didn't use, so every record costs at least a read and a
+    /// seek. For reading many records in a row, `lines` or `BufferedFile` is
+    /// much faster.
+    pub fn read_until(&mut self, delim: u8) -> Result<Option<Vec<u8>>, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut record = Vec::new();
+        let mut chunk = vec![0; MIN_READ_AHEAD];
+
+        loop {
+            let read = match self.underlying_file.read(&mut chunk) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.error(Operation::Read, e)),
+            };
+
+            if read == 0 {
+                if record.is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some(record));
+            }
+
+            if let Some(end) = chunk[..read].iter().position(|&byte| byte == delim) {
+                record.extend_from_slice(&chunk[..end]);
+                // give back whatever was read past the delimiter
+                let overshoot = (read - end - 1) as i64;
+                if overshoot > 0 {
+                    self.seek_from(std::io::SeekFrom::Current(-overshoot))?;
+                }
+                return Ok(Some(record));
+            }
+
+            record.extend_from_slice(&chunk[..read]);
+            // a long record, so read more at a time, up to a limit
+            if chunk.len() < MAX_READ_AHEAD {
+                chunk.resize(chunk.len() * 2, 0);
+            }
+        }
+    }
+
+    /// Iterate over the remaining records separated by `delim`, as `read_until` returns them.
+    pub fn split(&mut self, delim: u8) -> Split<'_> {
+        Split { file: self, delim }
+    }
+
+    /// Iterate over the rest of the file in chunks of exactly `size` bytes,
+    /// except for a shorter last chunk if the length isn't a multiple of `size`.
+    ///
+    /// A `size` of zero is rejected with `std::io::ErrorKind::InvalidInput`.
+    pub fn chunks(&mut self, size: usize) -> Result<Chunks<'_>, FileError> {
+        if size == 0 {
+            return Err(self.invalid_input(
+                Operation::Read,
+                "chunk size must be greater than zero".to_string(),
+            ));
+        }
+        Ok(Chunks { file: self, size })
+    }
+
+    /// Lazily iterate over the lines of the file through a buffered reader.
+    ///
+    /// The buffer may read ahead, so the file position after iterating is not
+    /// guaranteed to sit just past the last line returned.
+    pub fn lines(&mut self) -> Lines<'_> {
+        Lines {
+            file_name: self.file_name.clone(),
+            file_options: self.file_options,
+            inner: BufReader::new(self).lines(),
+        }
+    }
+
+    /// Read up to `len` bytes starting at `offset` without moving the file position.
+    ///
+    /// If the end of the file is reached first, the returned buffer is shorter
+    /// than `len`; an `offset` at or past the end yields an empty buffer. This
+    /// only needs `&self`, so several ranges can be read through a shared
+    /// `File`, from several threads if need be. Elsewhere than Unix the position
+    /// does move while reading and is put back afterwards, so there it is only
+    /// safe to read through the cursor concurrently from a separate handle.
+    pub fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut buf = vec![0; len];
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.read_at_into(offset + filled as u64, &mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.error(Operation::Read, e)),
+            }
+        }
+        buf.truncate(filled);
+        Ok(buf)
+    }
+
+    #[cfg(unix)]
+    fn read_at_into(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+
+        self.underlying_file.read_at(buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at_into(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+
+        let position = (&self.underlying_file).stream_position()?;
+        let read = self.underlying_file.seek_read(buf, offset);
+        (&self.underlying_file).seek(std::io::SeekFrom::Start(position))?;
+        read
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_at_into(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut file = &self.underlying_file;
+        let position = file.stream_position()?;
+        file.seek(std::io::SeekFrom::Start(offset))?;
+        let read = file.read(buf);
+        file.seek(std::io::SeekFrom::Start(position))?;
+        read
+    }
+
+    /// Write all of `data` starting at `offset` without moving the file position.
+    ///
+    /// Writing past the end of the file extends it, leaving any gap zero-filled.
+    /// Returns the number of bytes written. Like `read_at`, this only needs
+    /// `&self`, and elsewhere than Unix the position is moved and put back.
+    /// On Linux, files opened with `Append` ignore `offset` and write at the end.
+    pub fn write_at(&self, offset: u64, data: &[u8]) -> Result<usize, FileError> {
+        let mut written = 0;
+        while written < data.len() {
+            match self.write_at_from(offset + written as u64, &data[written..]) {
+                Ok(0) => {
+                    let e = std::io::Error::from(std::io::ErrorKind::WriteZero);
+                    return Err(self.error(Operation::Write, e));
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.error(Operation::Write, e)),
+            }
+        }
+        Ok(written)
+    }
+
+    #[cfg(unix)]
+    fn write_at_from(&self, offset: u64, data: &[u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+
+        self.underlying_file.write_at(data, offset)
+    }
+
+    #[cfg(windows)]
+    fn write_at_from(&self, offset: u64, data: &[u8]) -> std::io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+
+        let position = (&self.underlying_file).stream_position()?;
+        let written = self.underlying_file.seek_write(data, offset);
+        (&self.underlying_file).seek(std::io::SeekFrom::Start(position))?;
+        written
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn write_at_from(&self, offset: u64, data: &[u8]) -> std::io::Result<usize> {
+        let mut file = &self.underlying_file;
+        let position = file.stream_position()?;
+        file.seek(std::io::SeekFrom::Start(offset))?;
+        let written = file.write(data);
+        file.seek(std::io::SeekFrom::Start(position))?;
+        written
+    }
+
+    /// Seek to `pos`, returning the new offset from the start of the file.
+    pub fn seek_from(&mut self, pos: std::io::SeekFrom) -> Result<u64, FileError> {
+        self.underlying_file
+            .seek(pos)
+            .map_err(|e| self.error(Operation::Seek, e))
+    }
+
+    /// Report the current offset from the start of the file without moving it.
+    pub fn tell(&mut self) -> Result<u64, FileError> {
+        self.stream_position()
+    }
+
+    /// Report the current offset from the start of the file without moving it.
+    pub fn stream_position(&mut self) -> Result<u64, FileError> {
+        self.underlying_file
+            .stream_position()
+            .map_err(|e| self.error(Operation::Seek, e))
+    }
+
+    /// Seek back to the start of the file.
+    pub fn rewind(&mut self) -> Result<(), FileError> {
+        self.underlying_file
+            .rewind()
+            .map_err(|e| self.error(Operation::Seek, e))
+    }
+
+    /// Query the file's size, permissions and timestamps.
+    pub fn metadata(&self) -> Result<Metadata, FileError> {
+        let inner = self
+            .underlying_file
+            .metadata()
+            .map_err(|e| self.error(Operation::Metadata, e))?;
+        Ok(Metadata { inner })
+    }
+
+    /// The size of the file in bytes.
+    pub fn len(&self) -> Result<u64, FileError> {
+        Ok(self.metadata()?.len())
+    }
+
+    /// Whether the file is zero bytes long.
+    pub fn is_empty(&self) -> Result<bool, FileError> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn permissions(&self) -> Result<std::fs::Permissions, FileError> {
+        Ok(self.metadata()?.inner.permissions())
+    }
+
+    pub fn set_permissions(&mut self, perms: std::fs::Permissions) -> Result<(), FileError> {
+        self.underlying_file
+            .set_permissions(perms)
+            .map_err(|e| self.error(Operation::Metadata, e))
+    }
+
+    /// Set the file's Unix permission bits, e.g. `0o755`.
+    #[cfg(unix)]
+    pub fn set_mode(&mut self, mode: u32) -> Result<(), FileError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        self.set_permissions(std::fs::Permissions::from_mode(mode))
+    }
+
+    /// When the file was last modified.
+    pub fn modified(&self) -> Result<std::time::SystemTime, FileError> {
+        let metadata = self.metadata()?;
+        metadata
+            .inner
+            .modified()
+            .map_err(|e| self.error(Operation::Metadata, e))
+    }
+
+    /// When the file was created, on platforms that record it.
+    pub fn created(&self) -> Result<std::time::SystemTime, FileError> {
+        let metadata = self.metadata()?;
+        metadata
+            .inner
+            .created()
+            .map_err(|e| self.error(Operation::Metadata, e))
+    }
+
+    /// Read exactly `n` bytes from the current position.
+    ///
+    /// Fails with `std::io::ErrorKind::UnexpectedEof` if the file ends first.
+    pub fn read_n_bytes(&mut self, n: usize) -> Result<Vec<u8>, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut buf = vec![0; n];
+        self.underlying_file
+            .read_exact(&mut buf)
+            .map_err(|e| self.error(Operation::Read, e))?;
+        Ok(buf)
+    }
+
+    /// Write `data` at the end of the file, whether or not it was opened with `Append`.
+    ///
+    /// The file position is left at the new end of the file.
+    pub fn append<T>(&mut self, data: T) -> Result<(), FileError>
+    where
+        T: ToString,
+    {
+        self.require(FileOptions::Write)?;
+        self.seek_from(std::io::SeekFrom::End(0))?;
+        self.fwrite(data.to_string())?;
+        Ok(())
+    }
+
+    /// Truncate or zero-extend the file to `size` bytes. The file position is unchanged.
+    pub fn set_len(&mut self, size: u64) -> Result<(), FileError> {
+        self.require(FileOptions::Write)?;
+        self.underlying_file
+            .set_len(size)
+            .map_err(|e| self.error(Operation::Write, e))
+    }
+
+    /// Empty the file and move back to the start, ready to write new contents.
+    pub fn truncate(&mut self) -> Result<(), FileError> {
+        self.set_len(0)?;
+        self.rewind()
+    }
+
+    /// Copy the file's contents to `dest`, returning the number of bytes copied.
+    ///
+    /// This goes through `std::fs::copy`, so it uses the OS's fast path where one
+    /// exists and copies the permission bits. Copying a file onto itself is an error.
+    pub fn copy_to<P>(&self, dest: P) -> Result<u64, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let dest = dest.as_ref();
+        if self.is_same_file(dest) {
+            return Err(self.invalid_input(
+                Operation::Copy,
+                format!("cannot copy a file onto itself ({})", dest.display()),
+            ));
+        }
+
+        (&self.underlying_file)
+            .flush()
+            .map_err(|e| self.error(Operation::Flush, e))?;
+        std::fs::copy(&self.file_name, dest).map_err(|e| self.error(Operation::Copy, e))
+    }
+
+    /// Copy the rest of this file, from its current position, into `dest` at
+    /// its current position, returning the number of bytes copied.
+    ///
+    /// Unlike `copy_to` this works between open handles, so neither file needs
+    /// a path that can be reopened. It goes through `std::io::copy`, which uses
+    /// the OS's in-kernel copy where it can. Both positions are left just past
+    /// the copied data. This file must have been opened with `Read` and `dest`
+    /// with `Write`; errors from either side are reported against this file.
+    pub fn copy_stream(&mut self, dest: &mut File) -> Result<u64, FileError> {
+        self.require(FileOptions::Read)?;
+        dest.require(FileOptions::Write)?;
+
+        std::io::copy(&mut self.underlying_file, &mut dest.underlying_file)
+            .map_err(|e| self.error(Operation::Copy, e))
+    }
+
+    /// Like `copy_to`, but also carry over the modification time.
+    pub fn copy_to_preserving<P>(&self, dest: P) -> Result<u64, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let copied = self.copy_to(&dest)?;
+        let modified = self.modified()?;
+
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(dest)
+            .and_then(|dest| dest.set_modified(modified))
+            .map_err(|e| self.error(Operation::Copy, e))?;
+        Ok(copied)
+    }
+
+    /// Copy the whole file to `dest` in chunks of `chunk_size` bytes, calling
+    /// `progress` with the bytes copied so far and the total after each one.
+    ///
+    /// `progress` is called at least once, with `(0, 0)` for an empty file, and
+    /// the last call reports the copy complete. Returning `ControlFlow::Break`
+    /// from it cancels the copy with an error; then, or if the copy fails or
+    /// `progress` panics, the partly written `dest` is removed. Unlike
+    /// `copy_to`, permissions aren't copied. The file position is unchanged.
+    pub fn copy_to_with_progress<P, F>(
+        &mut self,
+        dest: P,
+        chunk_size: usize,
+        mut progress: F,
+    ) -> Result<u64, FileError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(u64, u64) -> ControlFlow<()>,
+    {
+        let dest = dest.as_ref();
+        self.require(FileOptions::Read)?;
+        if chunk_size == 0 {
+            return Err(self.invalid_input(
+                Operation::Copy,
+                "chunk size must be greater than zero".to_string(),
+            ));
+        }
+        if self.is_same_file(dest) {
+            return Err(self.invalid_input(
+                Operation::Copy,
+                format!("cannot copy a file onto itself ({})", dest.display()),
+            ));
+        }
+
+        let total = self.len()?;
+        // removed on drop unless the copy completes
+        let mut output = FileOptions::write_new().open(dest)?;
+        output.delete_on_drop = true;
+
+        let copied = self.keeping_position(|file| {
+            file.rewind()?;
+            let mut copied = 0;
+            let mut chunk = vec![0; chunk_size];
+            loop {
+                let read = match file.underlying_file.read(&mut chunk) {
+                    Ok(0) if copied > 0 => return Ok(copied),
+                    Ok(read) => read,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(file.error(Operation::Read, e)),
+                };
+                output
+                    .underlying_file
+                    .write_all(&chunk[..read])
+                    .map_err(|e| output.error(Operation::Write, e))?;
+                copied += read as u64;
+
+                if progress(copied, total.max(copied)).is_break() {
+                    return Err(file.error(
+                        Operation::Copy,
+                        std::io::Error::other("copy cancelled by the progress callback"),
+                    ));
+                }
+                if read == 0 {
+                    return Ok(copied);
+                }
+            }
+        })?;
+
+        output.delete_on_drop = false;
+        Ok(copied)
+    }
+
+    /// Rename the file on disk and remember the new name.
+    ///
+    /// The open handle stays valid, so reads and writes keep working afterwards.
+    pub fn rename<P>(&mut self, new_path: P) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let new_path = new_path.as_ref();
+        std::fs::rename(&self.file_name, new_path).map_err(|e| self.error(Operation::Rename, e))?;
+        self.file_name = new_path.to_path_buf();
+        Ok(())
+    }
+
+    /// Move the file to `new_path` and remember the new name, replacing any file already there.
+    ///
+    /// Unlike `rename`, this also works across filesystems by falling back to a
+    /// copy and delete, after which the handle is reopened at the same position.
+    pub fn rename_to<P>(&mut self, new_path: P) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let new_path = new_path.as_ref();
+        match self.rename(new_path) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+            result => return result,
+        }
+
+        let position = self.stream_position()?;
+        self.copy_to_preserving(new_path)?;
+
+        let old_path = self.file_name.clone();
+        self.reopen(new_path)?;
+        self.seek_from(std::io::SeekFrom::Start(position))?;
+
+        std::fs::remove_file(&old_path).map_err(|e| {
+            FileError::with_file(Operation::Delete, e, &old_path, self.file_options)
+        })?;
+        Ok(())
+    }
+
+    // replace this handle with one for `path`, opened with the same options
+    // minus those only meant for creating it, and keeping the line ending and
+    // the count of bytes written
+    pub(crate) fn reopen(&mut self, path: &Path) -> Result<(), FileError> {
+        let options = self.file_options
+            - FileOptions::Create
+            - FileOptions::ExclusiveCreate
+            - FileOptions::CreateDirs
+            - FileOptions::Truncate;
+        let mut reopened = options.open(path)?;
+        reopened.line_ending = self.line_ending;
+        reopened.delete_on_drop = std::mem::take(&mut self.delete_on_drop);
+        reopened.bytes_written = self.bytes_written;
+
+        *self = reopened;
+        Ok(())
+    }
+
+    /// Create a uniquely named file in the system temporary directory, opened
+    /// for reading and writing.
+    ///
+    /// The file is deleted when the `File` is dropped, unless `persist` moves it into place first.
+    pub fn temp() -> Result<File, FileError> {
+        File::temp_in(std::env::temp_dir())
+    }
+
+    /// Create a uniquely named file in `dir`, opened for reading and writing and
+    /// deleted on drop like `temp`.
+    pub fn temp_in<P>(dir: P) -> Result<File, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        let options = FileOptions::ExclusiveCreate | FileOptions::Read | FileOptions::Write;
+        let mut file = File::create_unique(dir.as_ref(), ".tmp", options)?;
+        file.delete_on_drop = true;
+        Ok(file)
+    }
+
+    // create a file in `dir` named `prefix` plus the process id and a random
+    // suffix, trying again on the rare clash; `options` must hold ExclusiveCreate
+    pub(crate) fn create_unique(
+        dir: &Path,
+        prefix: &str,
+        options: FileOptions,
+    ) -> Result<File, FileError> {
+        loop {
+            let name = format!(
+                "{}-{}-{:016x}",
+                prefix,
+                std::process::id(),
+                RandomState::new().build_hasher().finish()
+            );
+
+            match options.open(dir.join(name)) {
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Keep a file from `temp` by moving it to `path`, so it is no longer deleted on drop.
+    pub fn persist<P>(&mut self, path: P) -> Result<(), FileError>
+    where
+        P: AsRef<Path>,
+    {
+        self.rename_to(path)?;
+        self.delete_on_drop = false;
+        Ok(())
+    }
+
+    /// Open a second handle to the same file, with the same name, options and line ending.
+    ///
+    /// The handles share one open file description, so they share a position:
+    /// seeking, reading or writing through one moves the other too. Use
+    /// `read_at`/`write_at` or reopen the file for independent positions. A
+    /// clone of a `temp` file doesn't delete it on drop; only the original does.
+    pub fn try_clone(&self) -> Result<File, FileError> {
+        match self.underlying_file.try_clone() {
+            Ok(underlying_file) => Ok(File {
+                file_name: self.file_name.clone(),
+                file_options: self.file_options,
+                line_ending: self.line_ending,
+                delete_on_drop: false,
+                closed: false,
+                bytes_written: 0,
+                underlying_file,
+            }),
+            Err(e) => Err(self.error(Operation::Open, e)),
+        }
+    }
+
+    /// Wait until everything written, and all of the file's metadata, is on disk.
+    ///
+    /// Flushing only hands data to the OS, which may hold it in memory for a
+    /// while; after this returns it survives a crash or power loss.
+    pub fn sync_all(&self) -> Result<(), FileError> {
+        self.underlying_file
+            .sync_all()
+            .map_err(|e| self.error(Operation::Sync, e))
+    }
+
+    /// Like `sync_all`, but skip metadata that isn't needed to read the data
+    /// back, like the modification time, which usually saves a disk write.
+    pub fn sync_data(&self) -> Result<(), FileError> {
+        self.underlying_file
+            .sync_data()
+            .map_err(|e| self.error(Operation::Sync, e))
+    }
+
+    /// Flush the file, and sync it to disk if it was opened with `SyncOnClose`
+    /// or `SyncDataOnClose`, reporting any error instead of ignoring it as dropping does.
+    pub fn close(mut self) -> Result<(), FileError> {
+        let result = self.finish().map_err(|e| self.error(Operation::Close, e));
+        // done already, whether or not it worked, so dropping needn't try again
+        self.closed = true;
+        result
+    }
+
+    // the work of closing: flush, then sync if asked to and there could be anything to sync
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.underlying_file.flush()?;
+        if self.file_options.contains(FileOptions::Write) {
+            if self.file_options.contains(FileOptions::SyncOnClose) {
+                self.underlying_file.sync_all()?;
+            } else if self.file_options.contains(FileOptions::SyncDataOnClose) {
+                self.underlying_file.sync_data()?;
+            }
+        }
+        debug!("closed {}", self.file_name.display());
+        Ok(())
+    }
+
+    fn is_same_file(&self, other: &Path) -> bool {
+        match (self.file_name.canonicalize(), other.canonicalize()) {
+            (Ok(this), Ok(other)) => this == other,
+            _ => false,
+        }
+    }
+
+    /// Block until an exclusive advisory lock on the file is acquired.
+    ///
+    /// Locks are held per handle and released by `unlock` or when the `File` is dropped.
+    pub fn lock_exclusive(&self) -> Result<(), FileError> {
+        self.underlying_file
+            .lock()
+            .map_err(|e| self.error(Operation::Lock, e))
+    }
+
+    /// Block until a shared advisory lock on the file is acquired.
+    pub fn lock_shared(&self) -> Result<(), FileError> {
+        self.underlying_file
+            .lock_shared()
+            .map_err(|e| self.error(Operation::Lock, e))
+    }
+
+    /// Try to acquire an exclusive advisory lock without blocking.
+    ///
+    /// Returns `Ok(false)` if another handle holds a conflicting lock.
+    pub fn try_lock_exclusive(&self) -> Result<bool, FileError> {
+        match self.underlying_file.try_lock() {
+            Ok(()) => Ok(true),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(false),
+            Err(std::fs::TryLockError::Error(e)) => Err(self.error(Operation::Lock, e)),
+        }
+    }
+
+    /// Release any advisory lock held by this handle.
+    pub fn unlock(&self) -> Result<(), FileError> {
+        self.underlying_file
+            .unlock()
+            .map_err(|e| self.error(Operation::Lock, e))
+    }
+
+    // run `f`, then move back to the current position whether or not it succeeded
+    pub(crate) fn keeping_position<T, F>(&mut self, f: F) -> Result<T, FileError>
+    where
+        F: FnOnce(&mut File) -> Result<T, FileError>,
+    {
+        let position = self.stream_position()?;
+        let result = f(self);
+        self.seek_from(std::io::SeekFrom::Start(position))?;
+        result
+    }
+
+    // fail early with a clear message if the file wasn't opened with `option`
+    pub(crate) fn require(&self, option: FileOptions) -> Result<(), FileError> {
+        if self.file_options.contains(option) {
+            return Ok(());
+        }
+
+        let operation = if option == FileOptions::Read {
+            Operation::Read
+        } else if option == FileOptions::Write {
+            Operation::Write
+        } else {
+            Operation::Unknown
+        };
+        Err(self.invalid_input(operation, format!("file was not opened with {}", option)))
+    }
+
+    // write `data` as the whole of the file, for formats that can't be appended to
+    #[cfg(any(feature = "json", feature = "gzip"))]
+    pub(crate) fn replace_contents(&mut self, data: &[u8]) -> Result<(), FileError> {
+        self.require(FileOptions::Write)?;
+        if self.file_options.contains(FileOptions::Append) {
+            return Err(self.invalid_input(
+                Operation::Write,
+                "cannot replace the contents of a file opened with Append".to_string(),
+            ));
+        }
+
+        self.set_len(0)?;
+        self.rewind()?;
+        self.underlying_file
+            .write_all(data)
+            .map_err(|e| self.error(Operation::Write, e))
+    }
+
+    // `require` for the `std::io` traits, with the `FileError` carried inside the
+    // `std::io::Error` so converting it back recovers the file name and options
+    pub(crate) fn check(&self, option: FileOptions) -> std::io::Result<()> {
+        self.require(option)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+    }
+
+    // `error` for the `std::io` traits, carried inside a `std::io::Error` of the same kind
+    fn carry(&self, operation: Operation, error: std::io::Error) -> std::io::Error {
+        std::io::Error::new(error.kind(), self.error(operation, error))
+    }
+
+    // an error reported during `operation` on this file, tagged with its name and options
+    pub(crate) fn error(&self, operation: Operation, error: std::io::Error) -> FileError {
+        FileError::with_file(operation, error, &self.file_name, self.file_options)
+    }
+
+    // an error about a misuse of this file, rather than one reported by the OS
+    pub(crate) fn invalid_input(&self, operation: Operation, message: String) -> FileError {
+        FileError {
+            underlying_error: std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message.clone(),
+            ),
+            message,
+            file_name: self.file_name.clone(),
+            file_options: self.file_options,
+            operation,
+        }
+    }
+
+    /// Read up to `n` bytes from the current position, stopping early at the end of the file.
+    pub fn read_up_to(&mut self, n: usize) -> Result<Vec<u8>, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut buf = Vec::new();
+        (&mut self.underlying_file)
+            .take(n as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| self.error(Operation::Read, e))?;
+        Ok(buf)
+    }
+
+    /// The line ending used by the text APIs, `LineEnding::Lf` unless changed
+    /// here or with `FileOptions::line_ending`.
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// How many bytes have been written through this handle since it was opened.
+    ///
+    /// This counts everything written with `std::io::Write`, and so `Writer`'s
+    /// methods, `append`, `writeln`, `write_lines` and `write_text`, but not
+    /// positional writes like `write_at`. A `try_clone` starts again from zero.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Write `data` followed by the file's line ending.
+    pub fn writeln<T>(&mut self, data: T) -> Result<(), FileError>
+    where
+        T: std::fmt::Display,
+    {
+        let line_ending = self.line_ending.as_str();
+        write!(self, "{}{}", data, line_ending).map_err(|e| self.error(Operation::Write, e))
+    }
+
+    /// Read the rest of the file as text with every `\r\n` turned into `\n`,
+    /// unless the file's line ending is `LineEnding::Preserve`.
+    pub fn read_text(&mut self) -> Result<String, FileError> {
+        let text = self.fread()?;
+        match self.line_ending {
+            LineEnding::Preserve => Ok(text),
+            _ => Ok(LineEnding::Lf.convert(&text)),
+        }
+    }
+
+    /// Write `text` with its line endings converted to the file's line ending,
+    /// returning the number of bytes written.
+    pub fn write_text(&mut self, text: &str) -> Result<usize, FileError> {
+        let text = self.line_ending.convert(text);
+        self.fwrite(text)
+    }
+
+    /// Write each item followed by the file's line ending, including the last.
+    pub fn write_lines<I>(&mut self, lines: I) -> Result<(), FileError>
+    where
+        I: IntoIterator,
+        I::Item: std::fmt::Display,
+    {
+        for line in lines {
+            self.writeln(line)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Lines<'a> {
+    // kept apart from `inner`, which holds the only borrow of the file
+    file_name: PathBuf,
+    file_options: FileOptions,
+    inner: std::io::Lines<BufReader<&'a mut File>>,
+}
+
+impl Iterator for Lines<'_> {
+    type Item = Result<String, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|line| {
+            line.map_err(|e| {
+                FileError::with_file(Operation::Read, e, &self.file_name, self.file_options)
+            })
+        })
+    }
+}
+
+pub struct Split<'a> {
+    file: &'a mut File,
+    delim: u8,
+}
+
+impl Iterator for Split<'_> {
+    type Item = Result<Vec<u8>, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.file.read_until(self.delim).transpose()
+    }
+}
+
+pub struct Chunks<'a> {
+    file: &'a mut File,
+    size: usize,
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Result<Vec<u8>, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.file.read_up_to(self.size) {
+            Ok(chunk) if chunk.is_empty() => None,
+            result => Some(result),
+        }
+    }
+}
+
+pub struct Metadata {
+    inner: std::fs::Metadata,
+}
+
+impl Metadata {
+    pub fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.len() == 0
+    }
+
+    pub fn readonly(&self) -> bool {
+        self.inner.permissions().readonly()
+    }
+
+    pub fn modified(&self) -> Result<std::time::SystemTime, FileError> {
+        self.inner
+            .modified()
+            .map_err(|e| FileError::during(Operation::Metadata, e))
+    }
+
+    pub fn accessed(&self) -> Result<std::time::SystemTime, FileError> {
+        self.inner
+            .accessed()
+            .map_err(|e| FileError::during(Operation::Metadata, e))
+    }
+
+    pub fn created(&self) -> Result<std::time::SystemTime, FileError> {
+        self.inner
+            .created()
+            .map_err(|e| FileError::during(Operation::Metadata, e))
+    }
+}
+
+/// The kind of operation a `FileError` came from, as reported by `FileError::operation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    Open,
+    Read,
+    Write,
+    Flush,
+    Seek,
+    Sync,
+    Metadata,
+    Lock,
+    Copy,
+    Rename,
+    Delete,
+    Close,
+    /// The error was converted from a bare `std::io::Error`, so where it came from isn't known.
+    Unknown,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::Open => "open",
+            Operation::Read => "read",
+            Operation::Write => "write",
+            Operation::Flush => "flush",
+            Operation::Seek => "seek",
+            Operation::Sync => "sync",
+            Operation::Metadata => "metadata",
+            Operation::Lock => "lock",
+            Operation::Copy => "copy",
+            Operation::Rename => "rename",
+            Operation::Delete => "delete",
+            Operation::Close => "close",
+            Operation::Unknown => "unknown",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 // error struct
+/// The error type returned by every fallible operation in this crate.
+///
+/// It implements `std::error::Error`, so it composes with `?` and `Box<dyn Error>`:
+///
+/// ```no_run
+/// use file::FileOptions;
+/// use std::error::Error;
+///
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let file = FileOptions::Read.open("config.toml")?;
+///     println!("{} is {} bytes", file.file_name().display(), file.len()?);
+///     Ok(())
+/// }
+/// ```
+///
+/// When displayed, it includes the file name and options it was opened with and
+/// the operation that failed, e.g.
+/// `'config.toml' (Read): open failed: No such file or directory (os error 2)`.
 #[derive(Debug)]
 pub struct FileError {
     message: String,
+    file_name: PathBuf,
+    file_options: FileOptions,
+    operation: Operation,
     underlying_error: std::io::Error,
 }
 
+impl FileError {
+    // an error reported by the OS during `operation` on `file_name`
+    pub(crate) fn with_file(
+        operation: Operation,
+        error: std::io::Error,
+        file_name: &Path,
+        file_options: FileOptions,
+    ) -> FileError {
+        let error = match FileError::carried(error) {
+            Ok(carried) => return carried,
+            Err(error) => error,
+        };
+        FileError {
+            message: error.to_string(),
+            file_name: file_name.to_path_buf(),
+            file_options,
+            operation,
+            underlying_error: error,
+        }
+    }
+
+    // an error reported during `operation` where the file involved isn't known
+    pub(crate) fn during(operation: Operation, error: std::io::Error) -> FileError {
+        let error = match FileError::carried(error) {
+            Ok(carried) => return carried,
+            Err(error) => error,
+        };
+        FileError {
+            message: error.to_string(),
+            file_name: PathBuf::new(),
+            file_options: FileOptions::Uninitialized,
+            operation,
+            underlying_error: error,
+        }
+    }
+
+    // the `FileError` inside `error`, if `File::check` or `File::carry` made it, else `error` itself
+    fn carried(error: std::io::Error) -> Result<FileError, std::io::Error> {
+        if !error.get_ref().is_some_and(|inner| inner.is::<FileError>()) {
+            return Err(error);
+        }
+        match error
+            .into_inner()
+            .map(|inner| inner.downcast::<FileError>())
+        {
+            Some(Ok(carried)) => Ok(*carried),
+            _ => unreachable!("checked to hold a FileError above"),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The name of the file involved, or an empty path if it isn't known.
+    pub fn file_name(&self) -> &Path {
+        &self.file_name
+    }
+
+    /// The options the file was opened with, or `FileOptions::Uninitialized` if they aren't known.
+    pub fn options(&self) -> FileOptions {
+        self.file_options
+    }
+
+    /// The operation that failed, or `Operation::Unknown` for an error converted from a bare `std::io::Error`.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.underlying_error.kind()
+    }
+
+    pub fn into_io_error(self) -> std::io::Error {
+        self.underlying_error
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.kind() == std::io::ErrorKind::NotFound
+    }
+
+    pub fn is_permission_denied(&self) -> bool {
+        self.kind() == std::io::ErrorKind::PermissionDenied
+    }
+}
+
+/// Wrap a bare `std::io::Error`, e.g. one propagated with `?`.
+///
+/// The file name, options and operation are not known here, so the file name is
+/// left empty, the options are `FileOptions::Uninitialized` and the operation is
+/// `Operation::Unknown`. The exception is an error from `File`'s `std::io::Read`,
+/// `std::io::Write` or `std::io::Seek` impl, which carries the `FileError` it
+/// was made from and converts back to it.
 impl From<std::io::Error> for FileError {
     fn from(e: std::io::Error) -> Self {
-        FileError {
-            message: e.to_string(),
-            underlying_error: e,
+        FileError::during(Operation::Unknown, e)
+    }
+}
+
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.file_name.as_os_str().is_empty() {
+            write!(
+                f,
+                "'{}' ({}): ",
+                self.file_name.display(),
+                self.file_options
+            )?;
         }
+        if self.operation != Operation::Unknown {
+            write!(f, "{} failed: ", self.operation)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.underlying_error)
     }
 }
 
+/// Writing whole buffers, as text or as raw bytes.
+///
+/// Implemented for `File` and for anything implementing `std::io::Write`, so
+/// code written against it can be exercised in memory:
+///
+/// ```
+/// use file::Writer;
+///
+/// fn greet(sink: &mut impl Writer) {
+///     sink.fwrite("hello".to_string()).unwrap();
+/// }
+///
+/// let mut sink = Vec::new();
+/// greet(&mut sink);
+/// assert_eq!(sink, b"hello");
+/// ```
 pub trait Writer {
     fn fwrite(&mut self, buf: String) -> Result<usize, FileError>;
     fn fwrite_u8(&mut self, buf: &[u8]) -> Result<usize, FileError>;
     fn fflush(&mut self) -> Result<(), FileError>;
+
+    /// Write all of `buf` as raw bytes, returning its length.
+    fn fwrite_bytes(&mut self, buf: &[u8]) -> Result<usize, FileError> {
+        self.fwrite_u8(buf)
+    }
 }
 
+/// Reading the rest of a source in one call, as text or as raw bytes.
+///
+/// Implemented for `File` and for anything implementing `std::io::Read`, so
+/// code written against it can be exercised in memory:
+///
+/// ```
+/// use file::Reader;
+///
+/// fn word_count(source: &mut impl Reader) -> usize {
+///     source.fread().unwrap().split_whitespace().count()
+/// }
+///
+/// let mut source = std::io::Cursor::new("three short words");
+/// assert_eq!(word_count(&mut source), 3);
+/// ```
 pub trait Reader {
     fn fread(&mut self) -> Result<String, FileError>;
     fn fread_u8(&mut self) -> Result<Vec<u8>, FileError>;
+
+    /// Read the rest of the source as raw bytes, without any UTF-8 validation.
+    fn fread_bytes(&mut self) -> Result<Vec<u8>, FileError> {
+        self.fread_u8()
+    }
 }
 
 pub trait Seeker {
@@ -173,15 +1809,19 @@ where
     T: Write,
 {
     fn fwrite(&mut self, buf: String) -> Result<usize, FileError> {
-        self.write(buf.as_bytes()).map_err(FileError::from)
+        self.write_all(buf.as_bytes())
+            .map_err(|e| FileError::during(Operation::Write, e))?;
+        Ok(buf.len())
     }
 
     fn fwrite_u8(&mut self, buf: &[u8]) -> Result<usize, FileError> {
-        self.write(buf).map_err(FileError::from)
+        self.write_all(buf)
+            .map_err(|e| FileError::during(Operation::Write, e))?;
+        Ok(buf.len())
     }
 
     fn fflush(&mut self) -> Result<(), FileError> {
-        Write::flush(self).map_err(FileError::from)
+        Write::flush(self).map_err(|e| FileError::during(Operation::Flush, e))
     }
 }
 
@@ -192,13 +1832,15 @@ where
 {
     fn fread(&mut self) -> Result<String, FileError> {
         let mut buf = String::new();
-        self.read_to_string(&mut buf).map_err(FileError::from)?;
+        self.read_to_string(&mut buf)
+            .map_err(|e| FileError::during(Operation::Read, e))?;
         Ok(buf)
     }
 
     fn fread_u8(&mut self) -> Result<Vec<u8>, FileError> {
         let mut buf = Vec::new();
-        self.read_to_end(&mut buf).map_err(FileError::from)?;
+        self.read_to_end(&mut buf)
+            .map_err(|e| FileError::during(Operation::Read, e))?;
         Ok(buf)
     }
 }
@@ -209,41 +1851,120 @@ where
     T: Seek,
 {
     fn fseek(&mut self, pos: SeekFrom) -> Result<u64, FileError> {
-        Seek::seek(self, pos.into()).map_err(FileError::from)
+        Seek::seek(self, pos.into()).map_err(|e| FileError::during(Operation::Seek, e))
     }
 }
 
-// now, implement our traits for our File struct
-impl Writer for File {
-    fn fwrite(&mut self, buf: String) -> Result<usize, FileError> {
-        self.underlying_file.write(buf.as_bytes()).map_err(FileError::from)
+/// Flushes, and syncs with `FileOptions::SyncOnClose` or `SyncDataOnClose`, ignoring any errors;
+/// use `close` to see them.
+impl Drop for File {
+    fn drop(&mut self) {
+        if !self.closed {
+            if let Err(e) = self.finish() {
+                debug!("failed to close {}: {}", self.file_name.display(), e);
+            }
+        }
+        // the handle is still open here; that's fine on Unix, and on Windows
+        // std opens files with FILE_SHARE_DELETE, so the file goes once it closes
+        if self.delete_on_drop {
+            match std::fs::remove_file(&self.file_name) {
+                Ok(()) => {
+                    debug!("deleted {}", self.file_name.display());
+                }
+                Err(e) => {
+                    debug!("failed to delete {}: {}", self.file_name.display(), e);
+                }
+            }
+        }
     }
+}
 
-    fn fwrite_u8(&mut self, buf: &[u8]) -> Result<usize, FileError> {
-        self.underlying_file.write(buf).map_err(FileError::from)
+// now, implement the std traits for our File struct, which gives it ours too
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.check(FileOptions::Write)?;
+        let written = self
+            .underlying_file
+            .write(buf)
+            .map_err(|e| self.carry(Operation::Write, e))?;
+        self.bytes_written += written as u64;
+        debug!("wrote {} bytes to {}", written, self.file_name.display());
+        Ok(written)
     }
 
-    fn fflush(&mut self) -> Result<(), FileError> {
-        self.underlying_file.flush().map_err(FileError::from)
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.check(FileOptions::Write)?;
+        self.underlying_file
+            .write_all(buf)
+            .map_err(|e| self.carry(Operation::Write, e))?;
+        self.bytes_written += buf.len() as u64;
+        debug!("wrote {} bytes to {}", buf.len(), self.file_name.display());
+        Ok(())
+    }
+
+    /// Flushes, then syncs as far as the `Durability` the file was opened with asks.
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.underlying_file
+            .flush()
+            .map_err(|e| self.carry(Operation::Flush, e))?;
+        if self.file_options.contains(FileOptions::Write) {
+            let synced = match self.file_options.durability.unwrap_or_default() {
+                Durability::None => Ok(()),
+                Durability::Data => self.underlying_file.sync_data(),
+                Durability::All => self.underlying_file.sync_all(),
+            };
+            synced.map_err(|e| self.carry(Operation::Sync, e))?;
+        }
+        debug!("flushed {}", self.file_name.display());
+        Ok(())
     }
 }
 
-impl Reader for File {
-    fn fread(&mut self) -> Result<String, FileError> {
-        let mut buf = String::new();
-        self.underlying_file.read_to_string(&mut buf).map_err(FileError::from)?;
-        Ok(buf)
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.check(FileOptions::Read)?;
+        let read = self
+            .underlying_file
+            .read(buf)
+            .map_err(|e| self.carry(Operation::Read, e))?;
+        debug!("read {} bytes from {}", read, self.file_name.display());
+        Ok(read)
     }
 
-    fn fread_u8(&mut self) -> Result<Vec<u8>, FileError> {
-        let mut buf = Vec::new();
-        self.underlying_file.read_to_end(&mut buf).map_err(FileError::from)?;
-        Ok(buf)
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        self.check(FileOptions::Read)?;
+        let read = self
+            .underlying_file
+            .read_to_end(buf)
+            .map_err(|e| self.carry(Operation::Read, e))?;
+        debug!("read {} bytes from {}", read, self.file_name.display());
+        Ok(read)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> std::io::Result<usize> {
+        self.check(FileOptions::Read)?;
+        let read = self
+            .underlying_file
+            .read_to_string(buf)
+            .map_err(|e| self.carry(Operation::Read, e))?;
+        debug!("read {} bytes from {}", read, self.file_name.display());
+        Ok(read)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> std::io::Result<()> {
+        self.check(FileOptions::Read)?;
+        self.underlying_file
+            .read_exact(buf)
+            .map_err(|e| self.carry(Operation::Read, e))?;
+        debug!("read {} bytes from {}", buf.len(), self.file_name.display());
+        Ok(())
     }
 }
 
-impl Seeker for File {
-    fn fseek(&mut self, pos: SeekFrom) -> Result<u64, FileError> {
-        self.underlying_file.seek(pos.into()).map_err(FileError::from)
+impl Seek for File {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.underlying_file
+            .seek(pos)
+            .map_err(|e| self.carry(Operation::Seek, e))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,81 @@
+use std::io::{Read, Seek};
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const UTF8_BOM: &str = "\u{FEFF}";
+
+impl File {
+    /// Read the rest of the file as UTF-8, dropping a leading byte order mark if there is one.
+    pub fn read_no_bom(&mut self) -> Result<String, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut text = String::new();
+        self.underlying_file
+            .read_to_string(&mut text)
+            .map_err(|e| self.error(Operation::Read, e))?;
+
+        match text.strip_prefix(UTF8_BOM) {
+            Some(rest) => Ok(rest.to_string()),
+            None => Ok(text),
+        }
+    }
+
+    /// Whether the file starts with a UTF-8 byte order mark, leaving the position where it was.
+    pub fn has_bom(&mut self) -> Result<bool, FileError> {
+        self.require(FileOptions::Read)?;
+        self.keeping_position(|file| {
+            let mut start = Vec::new();
+            let read = file
+                .underlying_file
+                .rewind()
+                .and_then(|()| (&mut file.underlying_file).take(3).read_to_end(&mut start));
+            read.map_err(|e| file.error(Operation::Read, e))?;
+            Ok(start == UTF8_BOM.as_bytes())
+        })
+    }
+
+    /// Read the rest of the file as UTF-16 text.
+    ///
+    /// A leading byte order mark picks the byte order and is dropped; without
+    /// one the text is taken to be little-endian, as Windows writes it. An odd
+    /// number of bytes or an unpaired surrogate fails with
+    /// `std::io::ErrorKind::InvalidData`.
+    pub fn read_utf16(&mut self) -> Result<String, FileError> {
+        self.require(FileOptions::Read)?;
+        let mut bytes = Vec::new();
+        self.underlying_file
+            .read_to_end(&mut bytes)
+            .map_err(|e| self.error(Operation::Read, e))?;
+
+        if bytes.len() % 2 != 0 {
+            return Err(self.invalid_data(format!(
+                "UTF-16 text has an odd number of bytes ({})",
+                bytes.len()
+            )));
+        }
+
+        let (body, big_endian) = match bytes.as_slice() {
+            [0xFF, 0xFE, body @ ..] => (body, false),
+            [0xFE, 0xFF, body @ ..] => (body, true),
+            body => (body, false),
+        };
+        let units = body.chunks_exact(2).map(|pair| {
+            if big_endian {
+                u16::from_be_bytes([pair[0], pair[1]])
+            } else {
+                u16::from_le_bytes([pair[0], pair[1]])
+            }
+        });
+
+        char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(|e| self.invalid_data(e.to_string()))
+    }
+
+    // the contents aren't in the format they were read as
+    fn invalid_data(&self, message: String) -> FileError {
+        self.error(
+            Operation::Read,
+            std::io::Error::new(std::io::ErrorKind::InvalidData, message),
+        )
+    }
+}
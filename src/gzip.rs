@@ -0,0 +1,205 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const DEFAULT_LEVEL: u32 = 6;
+
+/// A `File` holding gzip-compressed data, which reads decompressed and writes compressed.
+///
+/// Files opened with `Write` are compressed into, and anything else is read
+/// from; a gzip stream can't be both at once. Unless opened with `Append`, a
+/// file being written is emptied first, as leftover bytes after the new
+/// stream would read as a broken second member. Writes are only complete once
+/// the gzip trailer is written by `close`, `into_inner` or dropping the
+/// `GzipFile`, which ignores errors, so call `close` to observe them.
+pub struct GzipFile {
+    inner: Inner,
+}
+
+enum Inner {
+    Reading(BufReader<MultiGzDecoder<BufReader<File>>>),
+    Writing(GzEncoder<File>),
+}
+
+impl GzipFile {
+    fn new(mut file: File, level: u32) -> Result<GzipFile, FileError> {
+        let options = file.file_options();
+        if options.contains(FileOptions::Read | FileOptions::Write) {
+            return Err(file.invalid_input(
+                Operation::Open,
+                "gzip files can be read or written, not both".to_string(),
+            ));
+        }
+
+        if options.contains(FileOptions::Write) {
+            if level > 9 {
+                return Err(file.invalid_input(
+                    Operation::Open,
+                    format!("gzip compression level must be 0-9, not {}", level),
+                ));
+            }
+            if !options.contains(FileOptions::Append) {
+                file.set_len(0)?;
+                file.rewind()?;
+            }
+            let encoder = GzEncoder::new(file, Compression::new(level));
+            return Ok(GzipFile {
+                inner: Inner::Writing(encoder),
+            });
+        }
+
+        let mut reader = BufReader::new(file);
+        let start = match reader.fill_buf() {
+            Ok(start) => start,
+            Err(e) => return Err(reader.get_ref().error(Operation::Read, e)),
+        };
+        if !start.is_empty() && !start.starts_with(&GZIP_MAGIC) {
+            let not_gzip =
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "not gzip-compressed data");
+            return Err(reader.get_ref().error(Operation::Open, not_gzip));
+        }
+
+        Ok(GzipFile {
+            inner: Inner::Reading(BufReader::new(MultiGzDecoder::new(reader))),
+        })
+    }
+
+    /// Iterate over the remaining decompressed lines, with line endings stripped.
+    pub fn lines(&mut self) -> impl Iterator<Item = Result<String, FileError>> + '_ {
+        let file_name = self.file().file_name().to_path_buf();
+        let file_options = self.file().file_options();
+
+        let reader: Box<dyn BufRead> = match &mut self.inner {
+            Inner::Reading(reader) => Box::new(reader),
+            Inner::Writing(_) => Box::new(std::io::empty()),
+        };
+        reader.lines().map(move |line| {
+            line.map_err(|e| FileError::with_file(Operation::Read, e, &file_name, file_options))
+        })
+    }
+
+    /// Write the gzip trailer, if writing, and hand back the plain `File`.
+    pub fn into_inner(self) -> Result<File, FileError> {
+        match self.inner {
+            Inner::Reading(reader) => Ok(reader.into_inner().into_inner().into_inner()),
+            Inner::Writing(encoder) => {
+                let file_name = encoder.get_ref().file_name().to_path_buf();
+                let file_options = encoder.get_ref().file_options();
+                encoder.finish().map_err(|e| {
+                    FileError::with_file(Operation::Write, e, &file_name, file_options)
+                })
+            }
+        }
+    }
+
+    /// Finish the gzip stream, if writing, and close the file, reporting any error.
+    pub fn close(self) -> Result<(), FileError> {
+        self.into_inner()?.close()
+    }
+
+    fn file(&self) -> &File {
+        match &self.inner {
+            Inner::Reading(reader) => reader.get_ref().get_ref().get_ref(),
+            Inner::Writing(encoder) => encoder.get_ref(),
+        }
+    }
+}
+
+impl Read for GzipFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            Inner::Reading(reader) => reader.read(buf),
+            Inner::Writing(_) => Err(not_opened_with(self.file(), FileOptions::Read)),
+        }
+    }
+}
+
+impl Write for GzipFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match &mut self.inner {
+            Inner::Writing(encoder) => encoder.write(buf),
+            Inner::Reading(_) => Err(not_opened_with(self.file(), FileOptions::Write)),
+        }
+    }
+
+    /// Compress everything written so far and flush it to the file, without
+    /// ending the gzip stream.
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.inner {
+            Inner::Writing(encoder) => encoder.flush(),
+            Inner::Reading(_) => Ok(()),
+        }
+    }
+}
+
+fn not_opened_with(file: &File, option: FileOptions) -> std::io::Error {
+    file.require(option).unwrap_err().into_io_error()
+}
+
+impl File {
+    /// Decompress the whole file, reading from the start whatever the current position.
+    ///
+    /// Data that isn't a valid gzip stream fails with `std::io::ErrorKind::InvalidData`.
+    pub fn read_gzip(&mut self) -> Result<Vec<u8>, FileError> {
+        self.require(FileOptions::Read)?;
+        self.rewind()?;
+        let mut compressed = Vec::new();
+        self.underlying_file
+            .read_to_end(&mut compressed)
+            .map_err(|e| self.error(Operation::Read, e))?;
+
+        let mut data = Vec::new();
+        flate2::read::MultiGzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut data)
+            .map_err(|e| {
+                let message = format!("invalid gzip data: {}", e);
+                self.error(
+                    Operation::Read,
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, message),
+                )
+            })?;
+        Ok(data)
+    }
+
+    /// Replace the contents of the file with `data`, gzip-compressed at the default level.
+    ///
+    /// As with `write_json`, the file must have been opened with `Write` and not `Append`.
+    pub fn write_gzip(&mut self, data: &[u8]) -> Result<(), FileError> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(DEFAULT_LEVEL));
+        let compressed = encoder
+            .write_all(data)
+            .and_then(|()| encoder.finish())
+            .map_err(|e| self.error(Operation::Write, e))?;
+        self.replace_contents(&compressed)
+    }
+
+    /// Treat the file as gzip-compressed, compressing writes at the default level.
+    ///
+    /// Reading a file that doesn't start with the gzip header fails here with
+    /// `std::io::ErrorKind::InvalidData`, rather than with garbage later.
+    pub fn gzip(self) -> Result<GzipFile, FileError> {
+        GzipFile::new(self, DEFAULT_LEVEL)
+    }
+
+    /// Treat the file as gzip-compressed, compressing writes at `level`, from
+    /// 0 (none) to 9 (smallest).
+    pub fn gzip_with_level(self, level: u32) -> Result<GzipFile, FileError> {
+        GzipFile::new(self, level)
+    }
+}
+
+impl FileOptions {
+    /// Open a gzip-compressed file, compressing writes at the default level.
+    pub fn open_gzip<P>(self, file_name: P) -> Result<GzipFile, FileError>
+    where
+        P: AsRef<Path>,
+    {
+        self.open(file_name)?.gzip()
+    }
+}
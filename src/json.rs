@@ -0,0 +1,54 @@
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+impl File {
+    /// Deserialize the whole file as JSON, reading from the start whatever the current position.
+    ///
+    /// Parse errors are reported as `std::io::ErrorKind::InvalidData`, tagged with the file name.
+    pub fn read_json<T>(&mut self) -> Result<T, FileError>
+    where
+        T: DeserializeOwned,
+    {
+        self.require(FileOptions::Read)?;
+        self.rewind()?;
+        let mut contents = Vec::new();
+        self.underlying_file
+            .read_to_end(&mut contents)
+            .map_err(|e| self.error(Operation::Read, e))?;
+
+        serde_json::from_slice(&contents).map_err(|e| {
+            self.error(
+                Operation::Read,
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )
+        })
+    }
+
+    /// Replace the contents of the file with `value` as compact JSON.
+    ///
+    /// The file must have been opened with `Write`. The old contents are always
+    /// discarded, whether or not the file was opened with `Truncate`, except for
+    /// files opened with `Append`: those can only be added to, so they are
+    /// rejected with `std::io::ErrorKind::InvalidInput` and left untouched.
+    pub fn write_json<T>(&mut self, value: &T) -> Result<(), FileError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let json = serde_json::to_vec(value).map_err(|e| self.error(Operation::Write, e.into()))?;
+        self.replace_contents(&json)
+    }
+
+    /// Replace the contents of the file with `value` as indented JSON, like `write_json`.
+    pub fn write_json_pretty<T>(&mut self, value: &T) -> Result<(), FileError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let json =
+            serde_json::to_vec_pretty(value).map_err(|e| self.error(Operation::Write, e.into()))?;
+        self.replace_contents(&json)
+    }
+}
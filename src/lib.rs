@@ -1,26 +1,2761 @@
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod atomic;
+mod binary;
+mod buffered;
+#[cfg(any(feature = "crc32", feature = "sha2"))]
+mod checksum;
+mod compare;
+#[cfg(feature = "csv")]
+mod csv;
 mod defs;
+mod encoding;
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "memory")]
+mod memory;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod tail;
 
+#[cfg(feature = "tokio")]
+pub use asynchronous::AsyncFile;
+pub use atomic::AtomicFile;
+pub use binary::{BinaryReader, BinaryWriter};
+pub use buffered::BufferedFile;
+pub use defs::{
+    Chunks, Durability, File, FileError, FileOptions, LineEnding, Lines, Metadata, Operation,
+    Reader, SeekFrom, Seeker, Split, Writer,
+};
+#[cfg(feature = "gzip")]
+pub use gzip::GzipFile;
+#[cfg(feature = "mmap")]
+pub use memmap2::{Mmap, MmapMut};
+#[cfg(feature = "memory")]
+pub use memory::MemoryFile;
+pub use tail::Follow;
 
+#[cfg(test)]
+// the original `open` tests spell out `assert_eq!(.., true)`; leave them as written
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
-    use crate::defs::{File, FileOptions, Reader, Writer};
-    use std::io::Read;
+    use crate::atomic::AtomicFile;
+    use crate::binary::{BinaryReader, BinaryWriter};
+    use crate::defs::{
+        Durability, File, FileError, FileOptions, LineEnding, Operation, Reader, SeekFrom, Seeker,
+        Writer,
+    };
+    #[cfg(feature = "gzip")]
+    use crate::gzip::GzipFile;
+    #[cfg(feature = "memory")]
+    use crate::memory::MemoryFile;
+    use std::io::{BufRead, BufReader, Read, Seek, Write};
+    use std::path::{Path, PathBuf};
 
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("file-test-{}", name))
+    }
+
+    fn fresh_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("file-test-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
 
     #[test]
     fn open() {
         let file = (FileOptions::Create | FileOptions::Write).open("file.txt");
         assert_eq!(file.is_ok(), true);
     }
-    
+
     #[test]
     fn open_and_attempt_read() {
         let file = (FileOptions::Read).open("README.md");
         assert_eq!(file.is_ok(), true);
         let mut file: File = file.unwrap();
-        
+
         // read the file
         let contents = file.fread();
 
         assert!(contents.is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_lines_mixed_endings() {
+        let path = temp_path("read_lines_mixed_endings.txt");
+        std::fs::write(&path, "first\nsecond\r\nthird\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_lines().unwrap(), vec!["first", "second", "third"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_lines_crlf_without_final_newline() {
+        let path = temp_path("read_lines_crlf_without_final_newline.txt");
+        std::fs::write(&path, "alpha\r\nbeta\r\ngamma").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_lines().unwrap(), vec!["alpha", "beta", "gamma"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_lines_empty() {
+        let path = temp_path("read_lines_empty.txt");
+        std::fs::write(&path, "").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert!(file.read_lines().unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fread_u8_non_utf8() {
+        let path = temp_path("fread_u8_non_utf8.bin");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite_u8(&[0xFF, 0xFE, 0x00]).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread_u8().unwrap(), vec![0xFF, 0xFE, 0x00]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lines_iterator() {
+        let path = temp_path("lines_iterator.txt");
+        std::fs::write(&path, "alpha\nbeta\r\ngamma").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let mut lines = file.lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "alpha");
+        assert_eq!(lines.next().unwrap().unwrap(), "beta");
+        assert_eq!(lines.next().unwrap().unwrap(), "gamma");
+        assert!(lines.next().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fread_u8_round_trip() {
+        let path = temp_path("fread_u8_round_trip.bin");
+        let data = [0x00, 0xFF, 0xFE, 0x80];
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite_u8(&data).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread_u8().unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn std_read_through_bufreader() {
+        let path = temp_path("std_read_through_bufreader.txt");
+        std::fs::write(&path, "hello\nworld\n").unwrap();
+
+        let file = FileOptions::Read.open(&path).unwrap();
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).unwrap();
+        assert_eq!(line, "hello\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fwrite_u8_large_non_utf8() {
+        let path = temp_path("fwrite_u8_large_non_utf8.bin");
+        let data: Vec<u8> = (0..1024 * 1024).map(|i| (i % 256) as u8).collect();
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        assert_eq!(file.fwrite_u8(&data).unwrap(), data.len());
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread_u8().unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn std_write_macro() {
+        let path = temp_path("std_write_macro.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        write!(file, "{}", 42).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "42");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn std_seek_from_end() {
+        let path = temp_path("std_seek_from_end.txt");
+        std::fs::write(&path, "abcdefgh").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.seek(std::io::SeekFrom::End(-4)).unwrap(), 4);
+
+        let mut buf = [0; 4];
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"efgh");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_at_keeps_position() {
+        let path = temp_path("read_at_keeps_position.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let mut buf = [0; 2];
+        file.read_exact(&mut buf).unwrap();
+
+        assert_eq!(file.read_at(4, 3).unwrap(), b"456");
+        assert_eq!(file.stream_position().unwrap(), 2);
+
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"23");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_at_through_shared_reference() {
+        let path = temp_path("read_at_through_shared_reference.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        let file = FileOptions::Read.open(&path).unwrap();
+        let shared = &file;
+        assert_eq!(shared.read_at(7, 3).unwrap(), b"789");
+        assert_eq!(shared.read_at(1, 2).unwrap(), b"12");
+
+        std::thread::scope(|scope| {
+            let first = scope.spawn(|| shared.read_at(0, 5).unwrap());
+            let second = scope.spawn(|| shared.read_at(5, 5).unwrap());
+            assert_eq!(first.join().unwrap(), b"01234");
+            assert_eq!(second.join().unwrap(), b"56789");
+        });
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_at_short_at_eof() {
+        let path = temp_path("read_at_short_at_eof.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        let file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_at(8, 5).unwrap(), b"89");
+        assert!(file.read_at(20, 5).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_at_patches_middle() {
+        let path = temp_path("write_at_patches_middle.txt");
+        let mut file =
+            (FileOptions::Create | FileOptions::Write | FileOptions::Read | FileOptions::Truncate)
+                .open(&path)
+                .unwrap();
+        file.fwrite("0123456789".to_string()).unwrap();
+
+        assert_eq!(file.write_at(3, b"abc").unwrap(), 3);
+        assert_eq!(file.stream_position().unwrap(), 10);
+        assert_eq!(file.read_at(0, 10).unwrap(), b"012abc6789");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_at_past_eof_zero_fills() {
+        let path = temp_path("write_at_past_eof_zero_fills.bin");
+        let mut file =
+            (FileOptions::Create | FileOptions::Write | FileOptions::Read | FileOptions::Truncate)
+                .open(&path)
+                .unwrap();
+        file.fwrite_u8(b"ab").unwrap();
+
+        file.write_at(4, b"cd").unwrap();
+        assert_eq!(file.read_at(0, 10).unwrap(), b"ab\0\0cd");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_at_patches_length_prefix() {
+        let path = temp_path("write_at_patches_length_prefix.bin");
+        let mut file = FileOptions::read_write()
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        file.write_u32_le(0).unwrap();
+        file.fwrite_bytes(b"body of the record").unwrap();
+
+        let header = &file;
+        assert_eq!(header.write_at(0, &18u32.to_le_bytes()).unwrap(), 4);
+        assert_eq!(file.stream_position().unwrap(), 22);
+
+        file.rewind().unwrap();
+        assert_eq!(file.read_u32_le().unwrap(), 18);
+        assert_eq!(file.fread().unwrap(), "body of the record");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn seek_from_past_eof() {
+        let path = temp_path("seek_from_past_eof.txt");
+        std::fs::write(&path, "abcd").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+
+        assert_eq!(file.seek_from(std::io::SeekFrom::End(6)).unwrap(), 10);
+        assert_eq!(file.seek_from(std::io::SeekFrom::Current(-3)).unwrap(), 7);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn seek_from_before_start() {
+        let mut file = FileOptions::Read.open("README.md").unwrap();
+        assert!(file.seek_from(std::io::SeekFrom::Current(-1)).is_err());
+    }
+
+    #[test]
+    fn tell_after_write() {
+        let path = temp_path("tell_after_write.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite("0123456789".to_string()).unwrap();
+
+        assert_eq!(file.tell().unwrap(), 10);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fseek_from_end() {
+        let path = temp_path("fseek_from_end.txt");
+        let mut file =
+            (FileOptions::Create | FileOptions::Write | FileOptions::Read | FileOptions::Truncate)
+                .open(&path)
+                .unwrap();
+        file.fwrite("the quick brown fox jumps".to_string())
+            .unwrap();
+
+        assert_eq!(file.fseek(SeekFrom::End(-10)).unwrap(), 15);
+        assert_eq!(file.fread().unwrap(), " fox jumps");
+
+        assert_eq!(
+            file.fseek(std::io::SeekFrom::Current(-5).into()).unwrap(),
+            20
+        );
+        assert_eq!(file.fread().unwrap(), "jumps");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rewind_and_read_again() {
+        let mut file = FileOptions::Read.open("README.md").unwrap();
+        let first = file.fread().unwrap();
+        assert_eq!(file.stream_position().unwrap(), first.len() as u64);
+
+        file.rewind().unwrap();
+        assert_eq!(file.stream_position().unwrap(), 0);
+        assert_eq!(file.fread().unwrap(), first);
+    }
+
+    #[test]
+    fn file_error_display() {
+        let error = FileOptions::Read.open("does-not-exist.txt").err().unwrap();
+        let message = error.to_string();
+
+        assert!(message.starts_with("'does-not-exist.txt' (Read): open failed: "));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn len_of_empty_file() {
+        let path = temp_path("len_of_empty_file.txt");
+        let file = FileOptions::write_new().open(&path).unwrap();
+
+        assert_eq!(file.len().unwrap(), 0);
+        assert!(file.is_empty().unwrap());
+        assert!(file.metadata().unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn len_and_metadata_of_written_file() {
+        let path = temp_path("len_and_metadata_of_written_file.txt");
+        std::fs::write(&path, "hello").unwrap();
+        let file = FileOptions::Read.open(&path).unwrap();
+
+        assert_eq!(file.len().unwrap(), 5);
+        assert!(!file.is_empty().unwrap());
+
+        let metadata = file.metadata().unwrap();
+        assert_eq!(metadata.len(), 5);
+        assert!(!metadata.readonly());
+        assert!(metadata.modified().is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_error_from_io_error() {
+        fn fails() -> Result<(), FileError> {
+            Err(std::io::Error::new(std::io::ErrorKind::NotFound, "boom"))?;
+            Ok(())
+        }
+
+        let error = fails().unwrap_err();
+        assert_eq!(error.to_string(), "boom");
+        assert_eq!(error.operation(), Operation::Unknown);
+    }
+
+    #[test]
+    fn file_error_operation() {
+        let error = FileOptions::Read.open("does-not-exist.txt").err().unwrap();
+        assert_eq!(error.operation(), Operation::Open);
+
+        let error = (FileOptions::Read | FileOptions::Truncate)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.operation(), Operation::Open);
+
+        let path = temp_path("file_error_operation.txt");
+        std::fs::write(&path, "write only").unwrap();
+        let mut file = FileOptions::Write.open(&path).unwrap();
+
+        let error = file.read_n_bytes(5).unwrap_err();
+        assert_eq!(error.operation(), Operation::Read);
+        assert_eq!(error.file_name(), path);
+        assert!(error.to_string().contains("read failed: "));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_getters() {
+        let options = FileOptions::Read;
+        let file = options.open("README.md").unwrap();
+
+        assert_eq!(file.file_name(), Path::new("README.md"));
+        assert_eq!(file.file_options(), options);
+    }
+
+    #[test]
+    fn file_path_options_and_debug() {
+        let options = FileOptions::Read;
+        let file = options.open("README.md").unwrap();
+
+        assert_eq!(file.path(), Path::new("README.md"));
+        assert_eq!(file.options(), options);
+        assert_eq!(
+            format!("{:?}", file),
+            "File { file_name: \"README.md\", file_options: FileOptions(Read), line_ending: Lf, .. }"
+        );
+    }
+
+    #[test]
+    fn file_error_accessors() {
+        let error = FileOptions::Read.open("does-not-exist.txt").err().unwrap();
+
+        assert_eq!(error.file_name(), Path::new("does-not-exist.txt"));
+        assert_eq!(error.options(), FileOptions::Read);
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert!(error.is_not_found());
+        assert!(!error.is_permission_denied());
+        let message = error.message().to_string();
+        assert_eq!(error.into_io_error().to_string(), message);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_error_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("file_error_permission_denied.txt");
+        std::fs::write(&path, "secret").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000)).unwrap();
+
+        let result = FileOptions::Read.open(&path);
+        std::fs::remove_file(&path).unwrap();
+        // root can open anything, so there is nothing to see
+        let Err(error) = result else { return };
+
+        assert!(error.is_permission_denied());
+        assert!(!error.is_not_found());
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert_eq!(error.operation(), Operation::Open);
+        assert_eq!(error.file_name(), path);
+        assert_eq!(error.options(), FileOptions::Read);
+    }
+
+    #[test]
+    fn read_n_bytes_exact_length() {
+        let path = temp_path("read_n_bytes_exact_length.txt");
+        std::fs::write(&path, "header").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_n_bytes(6).unwrap(), b"header");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_n_bytes_past_eof() {
+        let path = temp_path("read_n_bytes_past_eof.txt");
+        std::fs::write(&path, "header").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let error = file.read_n_bytes(7).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn std_io_copy_between_files() {
+        let source_path = temp_path("std_io_copy_source.txt");
+        let dest_path = temp_path("std_io_copy_dest.txt");
+        let mut source =
+            (FileOptions::Create | FileOptions::Write | FileOptions::Read | FileOptions::Truncate)
+                .open(&source_path)
+                .unwrap();
+        source.fwrite("copied through std::io".to_string()).unwrap();
+        source.rewind().unwrap();
+
+        let mut dest = FileOptions::write_new().open(&dest_path).unwrap();
+        assert_eq!(std::io::copy(&mut source, &mut dest).unwrap(), 22);
+
+        let mut dest = FileOptions::Read.open(&dest_path).unwrap();
+        assert_eq!(dest.fread().unwrap(), "copied through std::io");
+        std::fs::remove_file(&source_path).unwrap();
+        std::fs::remove_file(&dest_path).unwrap();
+    }
+
+    #[test]
+    fn lines_collect() {
+        let path = temp_path("lines_collect.txt");
+        std::fs::write(&path, "one\r\ntwo\nthree\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let lines = file.lines().collect::<Result<Vec<String>, FileError>>();
+        assert_eq!(lines.unwrap(), vec!["one", "two", "three"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_line_until_eof() {
+        let path = temp_path("read_line_until_eof.txt");
+        std::fs::write(&path, "first\r\n\nthird").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_line().unwrap().as_deref(), Some("first"));
+        assert_eq!(file.tell().unwrap(), 7);
+        assert_eq!(file.read_line().unwrap().as_deref(), Some(""));
+        assert_eq!(file.read_line().unwrap().as_deref(), Some("third"));
+        assert_eq!(file.read_line().unwrap(), None);
+        assert_eq!(file.read_line().unwrap(), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_line_longer_than_chunk() {
+        let path = temp_path("read_line_longer_than_chunk.txt");
+        let long = "x".repeat(1000);
+        std::fs::write(&path, format!("{}\nrest\n", long)).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_line().unwrap(), Some(long));
+        assert_eq!(file.fread().unwrap(), "rest\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_replaces_contents() {
+        let dir = fresh_temp_dir("write_atomic_replaces_contents");
+        let path = dir.join("config.txt");
+        File::write_atomic(&path, b"old").unwrap();
+        File::write_atomic(&path, b"new").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "new");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn atomic_file_abandoned() {
+        let dir = fresh_temp_dir("atomic_file_abandoned");
+        let path = dir.join("config.txt");
+        File::write_atomic(&path, b"original").unwrap();
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.fwrite("half-writ".to_string()).unwrap();
+        drop(file);
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "original");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn buffered_lines_count() {
+        let path = temp_path("buffered_lines_count.txt");
+        let contents: String = (0..100_000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, contents).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap().buffered();
+        let mut count = 0;
+        for (i, line) in file.lines().enumerate() {
+            assert_eq!(line.unwrap(), format!("line {}", i));
+            count += 1;
+        }
+        assert_eq!(count, 100_000);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn buffered_read_line_and_fread() {
+        let path = temp_path("buffered_read_line_and_fread.txt");
+        std::fs::write(&path, "header\r\nbody\nmore body").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap().buffered();
+        assert_eq!(file.read_line().unwrap().as_deref(), Some("header"));
+        assert_eq!(file.fread().unwrap(), "body\nmore body");
+        assert_eq!(file.read_line().unwrap(), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn validate_uninitialized() {
+        let error = FileOptions::new().validate().unwrap_err();
+        assert_eq!(error.message(), "FileOptions uninitialized");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn validate_truncate_and_append() {
+        let error = (FileOptions::Write | FileOptions::Truncate | FileOptions::Append)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.message(), "Truncate and Append cannot both be set");
+    }
+
+    #[test]
+    fn validate_truncate_without_write() {
+        let error = (FileOptions::Read | FileOptions::Truncate)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.message(), "Truncate requires Write");
+    }
+
+    #[test]
+    fn validate_exclusive_create_without_write() {
+        let error = (FileOptions::Read | FileOptions::ExclusiveCreate)
+            .validate()
+            .unwrap_err();
+        assert_eq!(error.message(), "ExclusiveCreate requires Write");
+    }
+
+    #[test]
+    fn open_create_without_write() {
+        let path = temp_path("create-without-write");
+        let _ = std::fs::remove_file(&path);
+
+        let error = (FileOptions::Read | FileOptions::Create)
+            .open(&path)
+            .err()
+            .unwrap();
+        assert_eq!(error.message(), "Create requires Write");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn validate_append_without_write() {
+        let error = FileOptions::Append.validate().unwrap_err();
+        assert_eq!(error.message(), "Append requires Write");
+    }
+
+    #[test]
+    fn open_reports_invalid_options() {
+        let error = (FileOptions::Read | FileOptions::Truncate)
+            .open("README.md")
+            .err()
+            .unwrap();
+        assert_eq!(error.file_name(), Path::new("README.md"));
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn append_preserves_contents() {
+        let path = temp_path("append_preserves_contents.txt");
+        std::fs::write(&path, "original").unwrap();
+
+        let mut file = (FileOptions::Read | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        file.append(", appended").unwrap();
+
+        file.rewind().unwrap();
+        assert_eq!(file.fread().unwrap(), "original, appended");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn append_requires_write() {
+        let mut file = FileOptions::Read.open("README.md").unwrap();
+        let error = file.append("nope").unwrap_err();
+
+        assert_eq!(error.message(), "file was not opened with Write");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn open_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = std::ffi::OsStr::from_bytes(b"file-test-non-utf8-\xFF.txt");
+        let path = std::env::temp_dir().join(name);
+        let file = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+
+        assert_eq!(file.file_name(), path);
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn len_after_flush() {
+        let path = temp_path("len_after_flush.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite("12345".to_string()).unwrap();
+        file.fflush().unwrap();
+
+        assert_eq!(file.len().unwrap(), 5);
+        assert!(file.modified().unwrap() <= std::time::SystemTime::now());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn create_with_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("create_with_mode.txt");
+        let _ = std::fs::remove_file(&path);
+        let file = (FileOptions::Create | FileOptions::Write)
+            .mode(0o600)
+            .open(&path)
+            .unwrap();
+
+        let permissions = file.underlying_file.metadata().unwrap().permissions();
+        assert_eq!(permissions.mode() & 0o777, 0o600);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn is_empty_tracks_writes() {
+        let path = temp_path("is_empty_tracks_writes.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        assert!(file.is_empty().unwrap());
+
+        file.fwrite("x".to_string()).unwrap();
+        assert!(!file.is_empty().unwrap());
+        assert_eq!(file.len().unwrap(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn try_lock_exclusive_while_locked() {
+        let path = temp_path("try_lock_exclusive_while_locked.txt");
+        let first = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        let second = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+
+        first.lock_exclusive().unwrap();
+        assert!(!second.try_lock_exclusive().unwrap());
+
+        first.unlock().unwrap();
+        assert!(second.try_lock_exclusive().unwrap());
+        second.unlock().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn shared_locks_through_shared_references() {
+        let path = temp_path("shared_locks_through_shared_references.txt");
+        let first = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        let second = FileOptions::Read.open(&path).unwrap();
+
+        // locking only needs `&File`, so a handle can be locked while borrowed elsewhere
+        let handles = [&first, &second];
+        for handle in handles {
+            handle.lock_shared().unwrap();
+        }
+        for handle in handles {
+            handle.unlock().unwrap();
+        }
+
+        first.lock_exclusive().unwrap();
+        first.unlock().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lock_released_on_drop() {
+        let path = temp_path("lock_released_on_drop.txt");
+        let first = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        let second = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+
+        first.lock_shared().unwrap();
+        assert!(!second.try_lock_exclusive().unwrap());
+
+        drop(first);
+        assert!(second.try_lock_exclusive().unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn set_len_grow_and_shrink() {
+        let path = temp_path("set_len_grow_and_shrink.bin");
+        let mut file =
+            (FileOptions::Create | FileOptions::Write | FileOptions::Read | FileOptions::Truncate)
+                .open(&path)
+                .unwrap();
+        file.fwrite_u8(b"abc").unwrap();
+
+        file.set_len(10).unwrap();
+        assert_eq!(file.read_at(0, 20).unwrap(), b"abc\0\0\0\0\0\0\0");
+
+        file.set_len(2).unwrap();
+        assert_eq!(file.read_at(0, 20).unwrap(), b"ab");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn set_len_requires_write() {
+        let mut file = FileOptions::Read.open("README.md").unwrap();
+        let error = file.set_len(0).unwrap_err();
+
+        assert_eq!(error.message(), "file was not opened with Write");
+    }
+
+    #[test]
+    fn truncate_empties_and_rewinds() {
+        let path = temp_path("truncate_empties_and_rewinds.txt");
+        let mut file = FileOptions::write_new().read(true).open(&path).unwrap();
+        file.fwrite("old contents".to_string()).unwrap();
+
+        file.set_len(3).unwrap();
+        file.rewind().unwrap();
+        assert_eq!(file.fread().unwrap(), "old");
+
+        file.truncate().unwrap();
+        assert_eq!(file.tell().unwrap(), 0);
+        file.fwrite("new".to_string()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"new");
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(
+            file.truncate().unwrap_err().message(),
+            "file was not opened with Write"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn copy_to_large_file() {
+        let source = temp_path("copy_to_large_file_source.bin");
+        let dest = temp_path("copy_to_large_file_dest.bin");
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i * 7 % 251) as u8).collect();
+        std::fs::write(&source, &data).unwrap();
+        let file = FileOptions::Read.open(&source).unwrap();
+
+        assert_eq!(file.copy_to(&dest).unwrap(), data.len() as u64);
+        assert_eq!(
+            FileOptions::Read.open(&dest).unwrap().fread_u8().unwrap(),
+            data
+        );
+        std::fs::remove_file(&source).unwrap();
+        std::fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn copy_stream_between_handles() {
+        let mut source = File::temp().unwrap();
+        let contents = "streamed between handles\n".repeat(10_000);
+        source.fwrite(contents.clone()).unwrap();
+        source.rewind().unwrap();
+        source.read_n_bytes(9).unwrap();
+
+        let path = temp_path("copy_stream_between_handles.txt");
+        let mut dest = FileOptions::write_new().open(&path).unwrap();
+        dest.fwrite("prefix:".to_string()).unwrap();
+
+        let copied = source.copy_stream(&mut dest).unwrap();
+        assert_eq!(copied, contents.len() as u64 - 9);
+        drop(dest);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("prefix:{}", &contents[9..])
+        );
+
+        let mut read_only = FileOptions::Read.open(&path).unwrap();
+        source.rewind().unwrap();
+        let error = source.copy_stream(&mut read_only).unwrap_err();
+        assert_eq!(error.message(), "file was not opened with Write");
+        assert_eq!(error.file_name(), path);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn copy_to_with_progress_reports_each_chunk() {
+        let dir = fresh_temp_dir("copy_to_with_progress_reports_each_chunk");
+        let source_path = dir.join("source.bin");
+        std::fs::write(&source_path, vec![7u8; 10_000]).unwrap();
+        let mut source = FileOptions::Read.open(&source_path).unwrap();
+        source.read_n_bytes(10).unwrap();
+
+        let mut calls = Vec::new();
+        let copied = source
+            .copy_to_with_progress(dir.join("copy.bin"), 4096, |copied, total| {
+                calls.push((copied, total));
+                std::ops::ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(copied, 10_000);
+        assert_eq!(
+            calls,
+            vec![(4096, 10_000), (8192, 10_000), (10_000, 10_000)]
+        );
+        assert_eq!(
+            std::fs::read(dir.join("copy.bin")).unwrap(),
+            vec![7u8; 10_000]
+        );
+        assert_eq!(source.tell().unwrap(), 10);
+
+        let empty_path = dir.join("empty.bin");
+        std::fs::write(&empty_path, b"").unwrap();
+        let mut calls = Vec::new();
+        FileOptions::Read
+            .open(&empty_path)
+            .unwrap()
+            .copy_to_with_progress(dir.join("empty-copy.bin"), 4096, |copied, total| {
+                calls.push((copied, total));
+                std::ops::ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(calls, vec![(0, 0)]);
+        assert!(dir.join("empty-copy.bin").exists());
+
+        let error = source
+            .copy_to_with_progress(dir.join("unused.bin"), 0, |_, _| {
+                std::ops::ControlFlow::Continue(())
+            })
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copy_to_with_progress_cancelled_removes_dest() {
+        let dir = fresh_temp_dir("copy_to_with_progress_cancelled_removes_dest");
+        let source_path = dir.join("source.bin");
+        std::fs::write(&source_path, vec![1u8; 10_000]).unwrap();
+        let mut source = FileOptions::Read.open(&source_path).unwrap();
+
+        let dest = dir.join("cancelled.bin");
+        let error = source
+            .copy_to_with_progress(&dest, 1000, |copied, _| {
+                if copied < 3000 {
+                    std::ops::ControlFlow::Continue(())
+                } else {
+                    std::ops::ControlFlow::Break(())
+                }
+            })
+            .unwrap_err();
+        assert_eq!(error.operation(), Operation::Copy);
+        assert!(!dest.exists());
+
+        let dest = dir.join("panicked.bin");
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            source.copy_to_with_progress(&dest, 1000, |_, _| panic!("progress bar broke"))
+        }));
+        assert!(result.is_err());
+        assert!(!dest.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copy_to_self_fails() {
+        let path = temp_path("copy_to_self_fails.txt");
+        std::fs::write(&path, "keep me").unwrap();
+        let file = FileOptions::Read.open(&path).unwrap();
+
+        let error = file.copy_to(&path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(
+            FileOptions::Read.open(&path).unwrap().fread().unwrap(),
+            "keep me"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn copy_to_preserving_keeps_mtime() {
+        let source = temp_path("copy_to_preserving_source.txt");
+        let dest = temp_path("copy_to_preserving_dest.txt");
+        std::fs::write(&source, "old news").unwrap();
+        let file = FileOptions::Read.open(&source).unwrap();
+
+        let modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        file.underlying_file.set_modified(modified).unwrap();
+
+        file.copy_to_preserving(&dest).unwrap();
+        assert_eq!(
+            FileOptions::Read.open(&dest).unwrap().modified().unwrap(),
+            modified
+        );
+        std::fs::remove_file(&source).unwrap();
+        std::fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn rename_open_file() {
+        let old_path = temp_path("rename_open_file_old.txt");
+        let new_path = temp_path("rename_open_file_new.txt");
+        let mut file = FileOptions::write_new().open(&old_path).unwrap();
+        file.fwrite("before ".to_string()).unwrap();
+
+        file.rename(&new_path).unwrap();
+        file.fwrite("after".to_string()).unwrap();
+
+        assert_eq!(file.file_name(), new_path);
+        assert!(!old_path.exists());
+        assert_eq!(
+            FileOptions::Read.open(&new_path).unwrap().fread().unwrap(),
+            "before after"
+        );
+        std::fs::remove_file(&new_path).unwrap();
+    }
+
+    #[test]
+    fn rename_to_updates_name() {
+        let old_path = temp_path("rename_to_updates_name_old.txt");
+        let new_path = temp_path("rename_to_updates_name_new.txt");
+        let mut file = FileOptions::write_new().open(&old_path).unwrap();
+
+        file.rename_to(&new_path).unwrap();
+        assert_eq!(file.file_name(), new_path);
+        assert!(!old_path.exists());
+        assert!(new_path.exists());
+        std::fs::remove_file(&new_path).unwrap();
+    }
+
+    #[test]
+    fn rename_to_replaces_existing() {
+        let old_path = temp_path("rename_to_replaces_existing_old.txt");
+        let new_path = temp_path("rename_to_replaces_existing_new.txt");
+        File::write_atomic(&new_path, b"existing").unwrap();
+        std::fs::write(&old_path, "replacement").unwrap();
+        let mut file = FileOptions::Read.open(&old_path).unwrap();
+
+        file.rename_to(&new_path).unwrap();
+        assert_eq!(
+            FileOptions::Read.open(&new_path).unwrap().fread().unwrap(),
+            "replacement"
+        );
+
+        let error = FileOptions::Read.open(&old_path).err().unwrap();
+        assert!(error.is_not_found());
+        std::fs::remove_file(&new_path).unwrap();
+    }
+
+    #[test]
+    fn copy_to_from_shared_reference() {
+        let source = temp_path("copy_to_from_shared_reference_source.txt");
+        let dest = temp_path("copy_to_from_shared_reference_dest.txt");
+        std::fs::write(&source, "duplicate me").unwrap();
+        let file = FileOptions::Read.open(&source).unwrap();
+
+        let file = &file;
+        assert_eq!(file.copy_to(&dest).unwrap(), 12);
+        assert_eq!(
+            std::fs::read(&source).unwrap(),
+            std::fs::read(&dest).unwrap()
+        );
+        std::fs::remove_file(&source).unwrap();
+        std::fs::remove_file(&dest).unwrap();
+    }
+
+    #[test]
+    fn convenience_constructors() {
+        for options in [
+            FileOptions::read_only(),
+            FileOptions::write_new(),
+            FileOptions::read_write(),
+            FileOptions::append_to(),
+        ] {
+            assert!(options.validate().is_ok());
+        }
+
+        let path = temp_path("convenience_constructors.txt");
+        let mut file = FileOptions::write_new().read(true).open(&path).unwrap();
+        file.fwrite("first".to_string()).unwrap();
+
+        let mut file = FileOptions::append_to().open(&path).unwrap();
+        file.fwrite(" second".to_string()).unwrap();
+
+        let mut file = FileOptions::read_only().open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "first second");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn default_and_access_constructors() {
+        assert!(FileOptions::default().validate().is_err());
+
+        let path = temp_path("default_and_access_constructors.txt");
+        std::fs::write(&path, "existing").unwrap();
+
+        for options in [
+            FileOptions::read_only(),
+            FileOptions::write_only(),
+            FileOptions::read_write(),
+        ] {
+            assert!(options.validate().is_ok());
+            assert!(options.open(&path).is_ok());
+        }
+
+        let mut file = FileOptions::write_only().open(&path).unwrap();
+        file.fwrite("E".to_string()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Existing");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn toggle_readonly() {
+        let path = temp_path("toggle_readonly.txt");
+        let mut file = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+
+        let original = file.permissions().unwrap();
+        let mut readonly = original.clone();
+        readonly.set_readonly(true);
+
+        file.set_permissions(readonly).unwrap();
+        assert!(file.metadata().unwrap().readonly());
+
+        file.set_permissions(original).unwrap();
+        assert!(!file.metadata().unwrap().readonly());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn set_mode_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("set_mode_bits.sh");
+        let mut file = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+
+        file.set_mode(0o755).unwrap();
+        assert_eq!(file.permissions().unwrap().mode() & 0o777, 0o755);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_options_from_fopen_modes() {
+        let modes = [
+            ("r", "Read"),
+            ("w", "Write|Create|Truncate"),
+            ("a", "Write|Create|Append"),
+            ("r+", "Read|Write"),
+            ("w+", "Read|Write|Create|Truncate"),
+            ("a+", "Read|Write|Create|Append"),
+            ("rb", "Read"),
+            ("r+b", "Read|Write"),
+        ];
+
+        for (mode, flags) in modes {
+            let options: FileOptions = mode.parse().unwrap();
+            assert_eq!(options.to_string(), flags, "mode {}", mode);
+            assert!(options.validate().is_ok(), "mode {}", mode);
+        }
+    }
+
+    #[test]
+    fn file_options_from_unknown_mode() {
+        let error = "rw".parse::<FileOptions>().unwrap_err();
+        assert_eq!(error.message(), "unknown mode string 'rw'");
+    }
+
+    #[test]
+    fn file_options_default_is_uninitialized() {
+        assert!(FileOptions::default().validate().is_err());
+        assert_eq!(FileOptions::default().read(true), FileOptions::Read);
+    }
+
+    #[test]
+    fn exists_is_file_is_dir() {
+        assert!(File::exists("README.md"));
+        assert!(File::is_file("README.md"));
+        assert!(!File::is_dir("README.md"));
+
+        assert!(File::exists("src"));
+        assert!(File::is_dir("src"));
+        assert!(!File::is_file("src"));
+
+        let missing = temp_path("exists_is_file_is_dir-5f0c9a1e");
+        assert!(!File::exists(&missing));
+        assert!(!File::is_file(&missing));
+        assert!(!File::is_dir(&missing));
+    }
+
+    #[test]
+    fn open_via_pathbuf_and_path() {
+        let path = PathBuf::from("src").join("lib.rs");
+        let file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.file_name(), path);
+
+        let file = FileOptions::Read.open(path.as_path()).unwrap();
+        assert_eq!(file.file_name(), path);
+
+        let error = FileOptions::Read
+            .open(path.with_extension("missing"))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("lib.missing"));
+    }
+
+    #[test]
+    fn buffered_writes_match_unbuffered() {
+        let buffered_path = temp_path("buffered_writes_match_buffered.txt");
+        let unbuffered_path = temp_path("buffered_writes_match_unbuffered.txt");
+
+        let mut buffered = FileOptions::write_new()
+            .open_buffered(&buffered_path)
+            .unwrap();
+        let mut unbuffered = FileOptions::write_new().open(&unbuffered_path).unwrap();
+        for i in 0..100_000 {
+            writeln!(buffered, "record {}", i).unwrap();
+            writeln!(unbuffered, "record {}", i).unwrap();
+        }
+        buffered.fflush().unwrap();
+
+        let contents = std::fs::read(&buffered_path).unwrap();
+        assert_eq!(contents, std::fs::read(&unbuffered_path).unwrap());
+        assert_eq!(
+            contents.iter().filter(|&&byte| byte == b'\n').count(),
+            100_000
+        );
+        std::fs::remove_file(&buffered_path).unwrap();
+        std::fs::remove_file(&unbuffered_path).unwrap();
+    }
+
+    #[test]
+    fn buffered_flush_reaches_disk() {
+        let path = temp_path("buffered_flush_reaches_disk.txt");
+        let mut file = FileOptions::write_new().open_buffered(&path).unwrap();
+
+        file.fwrite("pending".to_string()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+        file.fflush().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "pending");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn buffered_drop_flushes() {
+        let path = temp_path("buffered_drop_flushes.txt");
+        let mut file = FileOptions::write_new()
+            .open(&path)
+            .unwrap()
+            .buffered_with_capacity(64);
+        file.fwrite("written on drop".to_string()).unwrap();
+        drop(file);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "written on drop");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn buffered_into_inner() {
+        let path = temp_path("buffered_into_inner.txt");
+        File::write_atomic(&path, b"line one\nline two\n").unwrap();
+
+        let mut file = FileOptions::read_write().open_buffered(&path).unwrap();
+        assert_eq!(file.read_line().unwrap().as_deref(), Some("line one"));
+        file.fwrite("LINE".to_string()).unwrap();
+
+        let mut file = file.into_inner().unwrap();
+        assert_eq!(file.tell().unwrap(), 13);
+        file.rewind().unwrap();
+        assert_eq!(file.fread().unwrap(), "line one\nLINE two\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_through_dyn_reader() {
+        let path = temp_path("read_through_dyn_reader.txt");
+        File::write_atomic(&path, b"via the trait").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let reader: &mut dyn Reader = &mut file;
+        assert_eq!(reader.fread().unwrap(), "via the trait");
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let reader: &mut dyn Reader = &mut file;
+        assert_eq!(reader.fread_bytes().unwrap(), b"via the trait");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_up_to_stops_at_eof() {
+        let path = temp_path("read_up_to_stops_at_eof.txt");
+        File::write_atomic(&path, b"abcdef").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_up_to(0).unwrap(), b"");
+        assert_eq!(file.read_up_to(4).unwrap(), b"abcd");
+        assert_eq!(file.read_up_to(4).unwrap(), b"ef");
+        assert_eq!(file.read_up_to(4).unwrap(), b"");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_n_bytes_at_eof() {
+        let path = temp_path("read_n_bytes_at_eof.txt");
+        File::write_atomic(&path, b"abcdef").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_n_bytes(0).unwrap(), b"");
+        assert_eq!(file.read_n_bytes(6).unwrap(), b"abcdef");
+        assert_eq!(file.read_n_bytes(0).unwrap(), b"");
+
+        let error = file.read_n_bytes(1).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_through_dyn_writer() {
+        let path = temp_path("write_through_dyn_writer.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+
+        let writer: &mut dyn Writer = &mut file;
+        writer.fwrite("text, ".to_string()).unwrap();
+        writer.fwrite_bytes(b"bytes").unwrap();
+        writer.fflush().unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "text, bytes");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn writeln_and_write_lines() {
+        let path = temp_path("writeln_and_write_lines.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.writeln("header").unwrap();
+        file.write_lines([1, 2, 3]).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"header\n1\n2\n3\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn writeln_twice() {
+        let path = temp_path("writeln_twice.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.writeln("a").unwrap();
+        file.writeln('b').unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_lines_crlf() {
+        let path = temp_path("write_lines_crlf.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.set_line_ending(LineEnding::CrLf);
+        file.write_lines(["a", "b"]).unwrap();
+        file.write_lines(Vec::<String>::new()).unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"a\r\nb\r\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_lines_read_back() {
+        let path = temp_path("write_lines_read_back.txt");
+        let lines = vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()];
+
+        let mut file = FileOptions::write_new().read(true).open(&path).unwrap();
+        file.write_lines(&lines).unwrap();
+        file.rewind().unwrap();
+        assert_eq!(file.read_lines().unwrap(), lines);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn readme_usage() {
+        let path = temp_path("readme_usage.txt");
+        let _ = std::fs::remove_file(&path);
+
+        let mut file: File = FileOptions::new()
+            .write(true)
+            .create(true)
+            .open(&path)
+            .unwrap();
+        file.fwrite("world!".to_string()).unwrap();
+
+        let mut file: File = FileOptions::Read.open(&path).unwrap();
+        let string: String = file.fread().unwrap();
+        let _ = file.fseek(0.into());
+        let u8_vec: Vec<u8> = file.fread_u8().unwrap();
+        assert_eq!(string.as_bytes(), u8_vec);
+
+        let mut file: File = (FileOptions::Read | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        let mut string: String = file.fread().unwrap();
+        string = format!("Hello, {}", string);
+        let _ = file.fseek(0.into());
+        file.fwrite(string).unwrap();
+
+        let mut file: File = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), "Hello, world!");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn binary_header_round_trip() {
+        let path = temp_path("binary_header_round_trip.bin");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.write_u32_be(0x89504E47).unwrap();
+        file.write_u16_le(2).unwrap();
+        file.write_i32_le(-7).unwrap();
+        file.write_u64_be(u64::MAX - 1).unwrap();
+        file.write_i64_be(i64::MIN).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.len().unwrap(), 26);
+        assert_eq!(file.read_n_bytes(4).unwrap(), [0x89, 0x50, 0x4E, 0x47]);
+        file.rewind().unwrap();
+
+        assert_eq!(file.read_u32_be().unwrap(), 0x89504E47);
+        assert_eq!(file.read_u16_le().unwrap(), 2);
+        assert_eq!(file.read_i32_le().unwrap(), -7);
+        assert_eq!(file.read_u64_be().unwrap(), u64::MAX - 1);
+        assert_eq!(file.read_i64_be().unwrap(), i64::MIN);
+
+        let error = file.read_u16_be().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn validate_exclusive_create_and_truncate() {
+        let error = (FileOptions::Write | FileOptions::ExclusiveCreate | FileOptions::Truncate)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            error.message(),
+            "ExclusiveCreate and Truncate cannot both be set"
+        );
+    }
+
+    #[cfg(feature = "memory")]
+    #[test]
+    fn memory_file_reads_like_a_file() {
+        let mut file = MemoryFile::from("hello world");
+        file.fseek(SeekFrom::Start(6)).unwrap();
+        assert_eq!(file.fread().unwrap(), "world");
+        // at the end, reads return nothing rather than failing
+        assert_eq!(file.fread().unwrap(), "");
+        assert_eq!(file.fseek(SeekFrom::End(-5)).unwrap(), 6);
+    }
+
+    #[cfg(feature = "memory")]
+    #[test]
+    fn memory_file_writes_and_appends() {
+        let mut file = MemoryFile::from(b"abc".to_vec());
+        file.fwrite("X".to_string()).unwrap();
+        file.fseek(SeekFrom::Start(5)).unwrap();
+        file.fwrite("Y".to_string()).unwrap();
+        assert_eq!(file.into_contents(), b"Xbc\0\0Y");
+
+        let mut file = MemoryFile::from("abc").append(true);
+        file.fseek(SeekFrom::Start(0)).unwrap();
+        file.fwrite("def".to_string()).unwrap();
+        assert_eq!(file.contents(), b"abcdef");
+    }
+
+    #[test]
+    fn temp_file_deleted_on_drop() {
+        let mut file = File::temp().unwrap();
+        let path = file.file_name().to_path_buf();
+        assert!(path.starts_with(std::env::temp_dir()));
+
+        file.fwrite("scratch".to_string()).unwrap();
+        file.rewind().unwrap();
+        assert_eq!(file.fread().unwrap(), "scratch");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn temp_file_survives_persist() {
+        let dir = fresh_temp_dir("temp_file_survives_persist");
+        let first = File::temp_in(&dir).unwrap();
+        let mut second = File::temp_in(&dir).unwrap();
+        assert_ne!(first.file_name(), second.file_name());
+
+        second.fwrite("kept".to_string()).unwrap();
+        let kept = dir.join("kept.txt");
+        second.persist(&kept).unwrap();
+        drop(first);
+        drop(second);
+
+        assert_eq!(std::fs::read_to_string(&kept).unwrap(), "kept");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "json")]
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Config {
+        name: String,
+        retries: u32,
+        servers: Vec<Server>,
+    }
+
+    #[cfg(feature = "json")]
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Server {
+        host: String,
+        ports: Vec<u16>,
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let config = Config {
+            name: "cache".to_string(),
+            retries: 3,
+            servers: vec![
+                Server {
+                    host: "alpha".to_string(),
+                    ports: vec![80, 443],
+                },
+                Server {
+                    host: "beta".to_string(),
+                    ports: vec![],
+                },
+            ],
+        };
+
+        let path = temp_path("json_round_trip.json");
+        std::fs::write(&path, "x".repeat(1000)).unwrap();
+
+        // the old, longer contents are discarded even without Truncate
+        let mut file = FileOptions::read_write().open(&path).unwrap();
+        file.write_json_pretty(&config).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("\n  "));
+        assert_eq!(file.read_json::<Config>().unwrap(), config);
+
+        file.write_json(&config).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains('\n'));
+        assert_eq!(file.read_json::<Config>().unwrap(), config);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn read_json_fixture() {
+        let mut file = FileOptions::Read
+            .open("tests/fixtures/config.json")
+            .unwrap();
+        let config: Config = file.read_json().unwrap();
+        assert_eq!(config.name, "fixture");
+        assert_eq!(config.retries, 5);
+        assert_eq!(config.servers[0].host, "localhost");
+        assert_eq!(config.servers[0].ports, vec![8080]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn write_json_serialization_error() {
+        let path = temp_path("write_json_serialization_error.json");
+        std::fs::write(&path, "[]").unwrap();
+
+        // JSON object keys have to be strings
+        let value = std::collections::BTreeMap::from([((1, 2), 3)]);
+        let mut file = FileOptions::read_write().open(&path).unwrap();
+        let error = file.write_json(&value).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+        assert_eq!(file.read_json::<Vec<u8>>().unwrap(), Vec::<u8>::new());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_errors() {
+        let path = temp_path("json_errors.json");
+        std::fs::write(&path, "{\"name\": ").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let error = file.read_json::<Config>().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+        assert!(error.to_string().contains(&*path.to_string_lossy()));
+        assert!(file.write_json(&1).is_err());
+
+        let mut file = FileOptions::append_to().open(&path).unwrap();
+        let error = file.write_json(&1).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"name\": ");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn create_dirs_for_nested_path() {
+        let dir = fresh_temp_dir("create_dirs_for_nested_path");
+        let path = dir.join("logs").join("2024").join("05").join("out.log");
+
+        assert!(FileOptions::write_new().open(&path).is_err());
+
+        let options = FileOptions::write_new().create_dirs(true);
+        let mut file = options.open(&path).unwrap();
+        file.fwrite("hello".to_string()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello");
+
+        // the parents exist now, so opening again is just an open
+        assert!(options.open(&path).is_ok());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn create_dirs_keeps_other_errors() {
+        let dir = fresh_temp_dir("create_dirs_keeps_other_errors");
+        std::fs::create_dir(dir.join("taken")).unwrap();
+
+        // the parents are fine, but the final component is a directory
+        let error = (FileOptions::write_new() | FileOptions::CreateDirs)
+            .open(dir.join("taken"))
+            .err()
+            .unwrap();
+        assert_eq!(error.file_name(), dir.join("taken"));
+
+        let error = (FileOptions::Read | FileOptions::CreateDirs)
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            error.message(),
+            "CreateDirs requires Create or ExclusiveCreate"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn try_lock_exclusive_skips_when_busy() {
+        let path = temp_path("try_lock_exclusive_skips_when_busy.txt");
+        let holder = (FileOptions::Create | FileOptions::Write)
+            .open(&path)
+            .unwrap();
+        let worker = FileOptions::Read.open(&path).unwrap();
+
+        holder.lock_exclusive().unwrap();
+        // a worker sharing the handle across threads skips the job instead of waiting
+        let skipped = std::thread::scope(|scope| {
+            scope
+                .spawn(|| !worker.try_lock_exclusive().unwrap())
+                .join()
+                .unwrap()
+        });
+        assert!(skipped);
+
+        holder.unlock().unwrap();
+        assert!(worker.try_lock_exclusive().unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_indexes_into_file() {
+        let path = temp_path("mmap_indexes_into_file.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let file = FileOptions::Read.open(&path).unwrap();
+        let map = file.mmap().unwrap();
+        assert_eq!(map.len(), 10);
+        assert_eq!(map[3], b'3');
+        assert_eq!(&map[7..], b"789");
+
+        let file = FileOptions::Write.open(&path).unwrap();
+        assert_eq!(
+            file.mmap().unwrap_err().message(),
+            "file was not opened with Read"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_mut_writes_through_to_file() {
+        let path = temp_path("mmap_mut_writes_through_to_file.bin");
+        std::fs::write(&path, b"0123456789").unwrap();
+
+        let mut file = FileOptions::read_write().open(&path).unwrap();
+        let mut map = file.mmap_mut().unwrap();
+        map[2..5].copy_from_slice(b"abc");
+        map.flush().unwrap();
+        drop(map);
+
+        assert_eq!(file.fread().unwrap(), "01abc56789");
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let error = file.mmap_mut().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(error.message(), "file was not opened with Write");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn open_async_write_and_read() {
+        let path = temp_path("open_async_write_and_read.txt");
+
+        let mut file = FileOptions::write_new().open_async(&path).await.unwrap();
+        assert_eq!(file.write("hello async").await.unwrap(), 11);
+        drop(file);
+
+        let mut file = FileOptions::Read.open_async(&path).await.unwrap();
+        assert_eq!(file.read().await.unwrap(), "hello async");
+
+        let error = FileOptions::Read
+            .open_async(temp_path("open_async_missing.txt"))
+            .await
+            .err()
+            .unwrap();
+        assert!(error.is_not_found());
+        assert_eq!(error.file_name(), temp_path("open_async_missing.txt"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_bytes_seek_and_flush() {
+        let path = temp_path("async_bytes_seek_and_flush.bin");
+
+        let mut file = FileOptions::read_write()
+            .create(true)
+            .truncate(true)
+            .open_async(&path)
+            .await
+            .unwrap();
+        assert_eq!(file.write_bytes(b"0123456789").await.unwrap(), 10);
+        file.flush().await.unwrap();
+
+        assert_eq!(file.seek(std::io::SeekFrom::Start(4)).await.unwrap(), 4);
+        assert_eq!(file.read_bytes().await.unwrap(), b"456789");
+        assert_eq!(file.seek(std::io::SeekFrom::End(-2)).await.unwrap(), 8);
+        assert_eq!(file.read().await.unwrap(), "89");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_file_with_tokio_io() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = temp_path("async_file_with_tokio_io.txt");
+        let mut file = FileOptions::write_new().open_async(&path).await.unwrap();
+        file.write_all(b"through AsyncWrite").await.unwrap();
+        file.shutdown().await.unwrap();
+        drop(file);
+
+        let mut file = FileOptions::Read.open_async(&path).await.unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).await.unwrap();
+        assert_eq!(contents, "through AsyncWrite");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn async_rejects_missing_options() {
+        let path = temp_path("async_rejects_missing_options.txt");
+        let mut file = FileOptions::write_new().open_async(&path).await.unwrap();
+        let error = file.read_bytes().await.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(error.message(), "file was not opened with Read");
+        drop(file);
+
+        let mut file = FileOptions::Read.open_async(&path).await.unwrap();
+        let error = file.write("nope").await.unwrap_err();
+        assert_eq!(error.message(), "file was not opened with Write");
+        assert_eq!(error.operation(), Operation::Write);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn whole_file_helpers() {
+        let path = temp_path("whole_file_helpers.txt");
+        File::write_string(&path, "first").unwrap();
+        File::write_string(&path, "one").unwrap();
+        File::append_string(&path, " two").unwrap();
+
+        assert_eq!(File::read_to_string(&path).unwrap(), "one two");
+        assert_eq!(File::read_to_bytes(&path).unwrap(), b"one two");
+
+        let missing = temp_path("whole_file_helpers_missing.txt");
+        let error = File::read_to_string(&missing).unwrap_err();
+        assert!(error.is_not_found());
+        assert_eq!(error.file_name(), missing);
+
+        std::fs::write(&path, [0xff, 0xfe]).unwrap();
+        let error = File::read_to_string(&path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn crc32_of_known_input() {
+        let path = temp_path("crc32_of_known_input.txt");
+        std::fs::write(&path, "123456789").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        file.seek_from(std::io::SeekFrom::Start(4)).unwrap();
+        assert_eq!(file.crc32().unwrap(), 0xcbf43926);
+        // the position is untouched, so an in-progress read carries on
+        assert_eq!(file.fread().unwrap(), "56789");
+
+        // more than one chunk
+        std::fs::write(&path, vec![b'a'; 200 * 1024]).unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(
+            file.crc32().unwrap(),
+            crc32fast::hash(&vec![b'a'; 200 * 1024])
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn try_clone_reader_and_writer() {
+        let path = temp_path("try_clone_reader_and_writer.txt");
+        let mut reader = FileOptions::write_new().read(true).open(&path).unwrap();
+        reader.set_line_ending(LineEnding::CrLf);
+        let mut writer = reader.try_clone().unwrap();
+        assert_eq!(writer.file_name(), reader.file_name());
+        assert_eq!(writer.file_options(), reader.file_options());
+        assert_eq!(writer.line_ending(), LineEnding::CrLf);
+
+        std::thread::spawn(move || {
+            writer.writeln("first").unwrap();
+            writer.writeln("second").unwrap();
+        })
+        .join()
+        .unwrap();
+
+        // the clones share a position, which the writes left at the end
+        assert_eq!(reader.tell().unwrap(), 15);
+        assert_eq!(reader.read_at(0, 100).unwrap(), b"first\r\nsecond\r\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn try_clone_shares_position() {
+        let path = temp_path("try_clone_shares_position.txt");
+        std::fs::write(&path, "0123456789").unwrap();
+
+        let mut first = FileOptions::Read.open(&path).unwrap();
+        let mut second = first.try_clone().unwrap();
+        first.seek_from(std::io::SeekFrom::Start(4)).unwrap();
+
+        // a clone is the same open file, so seeking one moves the other
+        assert_eq!(second.tell().unwrap(), 4);
+        assert_eq!(second.read_up_to(2).unwrap(), b"45");
+        // read_at gives each caller its own offset without touching the shared one
+        assert_eq!(second.read_at(0, 4).unwrap(), b"0123");
+        assert_eq!(first.tell().unwrap(), 6);
+
+        // an independent position needs a separate open of the same file
+        let mut independent = first.file_options().open(first.file_name()).unwrap();
+        assert_eq!(independent.read_up_to(4).unwrap(), b"0123");
+        assert_eq!(first.tell().unwrap(), 6);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn written_data_survives_drop_and_close() {
+        let path = temp_path("written_data_survives_drop_and_close.txt");
+
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite("dropped".to_string()).unwrap();
+        drop(file);
+        assert_eq!(File::read_to_string(&path).unwrap(), "dropped");
+
+        let options = FileOptions::write_new().sync_on_close(true);
+        assert_eq!(options.to_string(), "Write|Create|Truncate|SyncOnClose");
+        let mut file = options.open(&path).unwrap();
+        file.fwrite("synced".to_string()).unwrap();
+        drop(file);
+        assert_eq!(File::read_to_string(&path).unwrap(), "synced");
+
+        let mut file = options.open(&path).unwrap();
+        file.fwrite("closed".to_string()).unwrap();
+        file.close().unwrap();
+        assert_eq!(File::read_to_string(&path).unwrap(), "closed");
+
+        // nothing to sync on a read-only handle, so closing it still succeeds
+        let file = (FileOptions::Read | FileOptions::SyncOnClose)
+            .open(&path)
+            .unwrap();
+        file.close().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn sha256_of_known_input() {
+        let path = temp_path("sha256_of_known_input.txt");
+        std::fs::write(&path, "abc").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        file.seek_from(std::io::SeekFrom::Start(1)).unwrap();
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(file.sha256_hex().unwrap(), expected);
+        assert_eq!(file.sha256().unwrap()[..4], [0xba, 0x78, 0x16, 0xbf]);
+        assert_eq!(file.fread().unwrap(), "bc");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn head_and_tail() {
+        let path = temp_path("head_and_tail.txt");
+        std::fs::write(&path, "one\r\ntwo\nthree\nfour\nfive").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        file.seek_from(std::io::SeekFrom::Start(5)).unwrap();
+        assert_eq!(file.head(2).unwrap(), vec!["one", "two"]);
+        // no trailing newline, so the last line is still a line
+        assert_eq!(file.tail(2).unwrap(), vec!["four", "five"]);
+        assert_eq!(file.fread().unwrap(), "two\nthree\nfour\nfive");
+
+        // asking for more lines than there are
+        assert_eq!(file.head(10).unwrap().len(), 5);
+        assert_eq!(
+            file.tail(10).unwrap(),
+            vec!["one", "two", "three", "four", "five"]
+        );
+        assert!(file.tail(0).unwrap().is_empty());
+
+        std::fs::write(&path, "").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert!(file.head(3).unwrap().is_empty());
+        assert!(file.tail(3).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tail_across_blocks() {
+        let path = temp_path("tail_across_blocks.txt");
+        let lines: Vec<String> = (0..50_000).map(|i| format!("line {}", i)).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.tail(3).unwrap(), lines[49_997..]);
+        assert_eq!(file.tail(20_000).unwrap(), lines[30_000..]);
+        assert_eq!(file.head(20_000).unwrap(), lines[..20_000]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn head_and_tail_single_huge_line() {
+        let path = temp_path("head_and_tail_single_huge_line.txt");
+        let line = "é".repeat(150 * 1024);
+        std::fs::write(&path, format!("{}\n", line)).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.tail(1).unwrap(), vec![line.clone()]);
+        assert_eq!(file.head(1).unwrap(), vec![line]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn head_stops_before_a_character_split_across_blocks() {
+        let path = temp_path("head_stops_before_a_character_split_across_blocks.txt");
+        // the odd-length first line puts a block boundary inside an 'é'
+        std::fs::write(&path, format!("a\nx{}", "é".repeat(40_000))).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.head(1).unwrap(), vec!["a"]);
+        assert_eq!(file.head(0).unwrap(), Vec::<String>::new());
+        assert_eq!(file.head(2).unwrap()[1].len(), 1 + 2 * 40_000);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn follow_lines_appended_by_another_thread() {
+        let path = temp_path("follow_lines_appended_by_another_thread.log");
+        std::fs::write(&path, "already there\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        file.seek_from(std::io::SeekFrom::End(0)).unwrap();
+
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            for line in ["first\n", "sec", "ond\r\n", "third\n"] {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                File::append_string(&writer_path, line).unwrap();
+            }
+        });
+
+        let lines: Vec<String> = file
+            .follow(std::time::Duration::from_millis(5))
+            .take(3)
+            .map(Result::unwrap)
+            .collect();
+        writer.join().unwrap();
+        assert_eq!(lines, vec!["first", "second", "third"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn follow_streams_a_large_backlog() {
+        let path = temp_path("follow_streams_a_large_backlog.log");
+        std::fs::write(&path, "a log line\n".repeat(200_000)).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let mut follow = file.follow(std::time::Duration::from_millis(10));
+        assert_eq!(follow.next().unwrap().unwrap(), "a log line");
+        drop(follow);
+
+        // only the first block was read to yield the first line
+        assert!(file.tell().unwrap() <= 8 * 1024);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn follow_after_truncation_and_stop() {
+        let path = temp_path("follow_after_truncation_and_stop.log");
+        std::fs::write(&path, "a long line before truncation\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let mut follow = file.follow(std::time::Duration::from_millis(5));
+        assert_eq!(
+            follow.next().unwrap().unwrap(),
+            "a long line before truncation"
+        );
+
+        let stop = follow.stop_handle();
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            File::write_string(&writer_path, "restarted\n").unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+
+        assert_eq!(follow.next().unwrap().unwrap(), "restarted");
+        assert!(follow.next().is_none());
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_rotated_file() {
+        let dir = fresh_temp_dir("follow_rotated_file");
+        let path = dir.join("app.log");
+        std::fs::write(&path, "old\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let mut follow = file.follow(std::time::Duration::from_millis(5));
+        assert_eq!(follow.next().unwrap().unwrap(), "old");
+
+        std::fs::rename(&path, dir.join("app.log.1")).unwrap();
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(follow.next().unwrap().unwrap(), "new");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn checksum_crc32_known_vectors() {
+        let path = temp_path("checksum_crc32_known_vectors.txt");
+        std::fs::write(&path, "").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.crc32().unwrap(), 0);
+
+        std::fs::write(&path, "hello world").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.crc32().unwrap(), 0x0d4a1185);
+        assert_eq!(file.tell().unwrap(), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn checksum_sha256_known_vectors() {
+        let path = temp_path("checksum_sha256_known_vectors.txt");
+        std::fs::write(&path, "").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(
+            file.sha256_hex().unwrap(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+
+        std::fs::write(&path, "hello world").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let digest = file.sha256().unwrap();
+        assert_eq!(digest[..4], [0xb9, 0x4d, 0x27, 0xb9]);
+        assert_eq!(
+            file.sha256_hex().unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn contents_equal() {
+        let dir = fresh_temp_dir("contents_equal");
+        let data: Vec<u8> = (0..200 * 1024).map(|i| (i % 253) as u8).collect();
+        let mut changed = data.clone();
+        *changed.last_mut().unwrap() ^= 1;
+
+        std::fs::write(dir.join("a"), &data).unwrap();
+        std::fs::write(dir.join("b"), &data).unwrap();
+        std::fs::write(dir.join("changed"), &changed).unwrap();
+        std::fs::write(dir.join("short"), &data[..1000]).unwrap();
+        let open = |name: &str| FileOptions::Read.open(dir.join(name)).unwrap();
+
+        let mut a = open("a");
+        a.seek_from(std::io::SeekFrom::Start(10)).unwrap();
+        assert!(a.contents_equal(&mut open("b")).unwrap());
+        assert_eq!(a.tell().unwrap(), 10);
+
+        assert!(!a.contents_equal(&mut open("changed")).unwrap());
+        assert!(!a.contents_equal(&mut open("short")).unwrap());
+        // the same file through a second handle
+        assert!(a.contents_equal(&mut open("a")).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn read_utf16_byte_orders() {
+        let path = temp_path("read_utf16_byte_orders.txt");
+        let text = "héllo 🌍";
+
+        let mut le = vec![0xFF, 0xFE];
+        le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        std::fs::write(&path, &le).unwrap();
+        assert_eq!(
+            FileOptions::Read.open(&path).unwrap().read_utf16().unwrap(),
+            text
+        );
+
+        let mut be = vec![0xFE, 0xFF];
+        be.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        std::fs::write(&path, &be).unwrap();
+        assert_eq!(
+            FileOptions::Read.open(&path).unwrap().read_utf16().unwrap(),
+            text
+        );
+
+        // no BOM means little-endian
+        std::fs::write(&path, &le[2..]).unwrap();
+        assert_eq!(
+            FileOptions::Read.open(&path).unwrap().read_utf16().unwrap(),
+            text
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_utf16_invalid() {
+        let path = temp_path("read_utf16_invalid.txt");
+        std::fs::write(&path, [0xFF, 0xFE, b'a', 0, b'b']).unwrap();
+        let error = FileOptions::Read
+            .open(&path)
+            .unwrap()
+            .read_utf16()
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+
+        // an unpaired high surrogate
+        std::fs::write(&path, [0x00, 0xD8, b'a', 0]).unwrap();
+        let error = FileOptions::Read
+            .open(&path)
+            .unwrap()
+            .read_utf16()
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn utf8_bom() {
+        let path = temp_path("utf8_bom.csv");
+        std::fs::write(&path, "\u{FEFF}name,age\n").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        file.seek_from(std::io::SeekFrom::Start(2)).unwrap();
+        assert!(file.has_bom().unwrap());
+        assert_eq!(file.tell().unwrap(), 2);
+        file.rewind().unwrap();
+        assert_eq!(file.read_no_bom().unwrap(), "name,age\n");
+
+        std::fs::write(&path, "name,age\n").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert!(!file.has_bom().unwrap());
+        assert_eq!(file.read_no_bom().unwrap(), "name,age\n");
+
+        std::fs::write(&path, "").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert!(!file.has_bom().unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_until_zero_separated_records() {
+        let path = temp_path("read_until_zero_separated_records.bin");
+        std::fs::write(&path, b"one\0\0two;2\0three").unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_until(0).unwrap().unwrap(), b"one");
+        assert_eq!(file.read_until(0).unwrap().unwrap(), b"");
+        // the position is just past the delimiter, so other reads line up
+        assert_eq!(file.read_up_to(3).unwrap(), b"two");
+        assert_eq!(file.read_until(b';').unwrap().unwrap(), b"");
+        // the last record has no delimiter after it
+        let rest: Vec<Vec<u8>> = file.split(0).map(Result::unwrap).collect();
+        assert_eq!(rest, vec![b"2".to_vec(), b"three".to_vec()]);
+        assert!(file.read_until(0).unwrap().is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn split_large_records() {
+        let path = temp_path("split_large_records.bin");
+        let big: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 255 + 1) as u8).collect();
+        let mut data = big.clone();
+        data.push(0);
+        data.extend_from_slice(b"small\0");
+        data.extend_from_slice(&big);
+        std::fs::write(&path, &data).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let records: Vec<Vec<u8>> = file.split(0).map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records[0] == big && records[2] == big);
+        assert_eq!(records[1], b"small");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn chunks_with_partial_last_chunk() {
+        let path = temp_path("chunks_with_partial_last_chunk.bin");
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let chunks: Vec<Vec<u8>> = file.chunks(4096).unwrap().map(Result::unwrap).collect();
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![4096, 4096, 1808]
+        );
+        assert_eq!(chunks.concat(), data);
+
+        let error = file.chunks(0).err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_round_trip() {
+        let path = temp_path("gzip_round_trip.txt.gz");
+        let text = "compressible line\n".repeat(1000);
+
+        let mut file: GzipFile = FileOptions::write_new()
+            .open(&path)
+            .unwrap()
+            .gzip_with_level(9)
+            .unwrap();
+        file.fwrite(text.clone()).unwrap();
+        file.fflush().unwrap();
+        file.close().unwrap();
+        assert!(std::fs::metadata(&path).unwrap().len() < text.len() as u64 / 10);
+
+        // appending adds a second gzip member, which reads as a continuation
+        let mut file = FileOptions::append_to().open_gzip(&path).unwrap();
+        file.fwrite("appended\n".to_string()).unwrap();
+        drop(file);
+
+        let mut file = FileOptions::Read.open_gzip(&path).unwrap();
+        assert_eq!(file.fread().unwrap(), text + "appended\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_fixture_from_cli() {
+        let mut file = FileOptions::Read
+            .open_gzip("tests/fixtures/hello.txt.gz")
+            .unwrap();
+        let lines: Vec<String> = file.lines().map(Result::unwrap).collect();
+        assert_eq!(lines, vec!["hello from the gzip CLI", "second line"]);
+
+        let mut file = FileOptions::Read
+            .open_gzip("tests/fixtures/hello.txt.gz")
+            .unwrap();
+        assert_eq!(
+            file.fread_u8().unwrap(),
+            b"hello from the gzip CLI\nsecond line\n"
+        );
+        assert!(file.fwrite("nope".to_string()).is_err());
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_rejects_plain_files() {
+        let error = FileOptions::Read.open_gzip("README.md").err().unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.message(), "not gzip-compressed data");
+        assert_eq!(error.file_name(), Path::new("README.md"));
+
+        let error = FileOptions::read_write()
+            .open_gzip("README.md")
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn read_gzip_write_gzip_round_trip() {
+        let path = temp_path("read_gzip_write_gzip_round_trip.bin.gz");
+        let data: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.write_gzip(&data).unwrap();
+        drop(file);
+        assert!(std::fs::metadata(&path).unwrap().len() < data.len() as u64);
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_gzip().unwrap(), data);
+
+        let error = FileOptions::Read
+            .open("README.md")
+            .unwrap()
+            .read_gzip()
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.message().starts_with("invalid gzip data: "));
+        assert_eq!(error.operation(), Operation::Read);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn csv_records_with_quoted_fields() {
+        let path = temp_path("csv_records_with_quoted_fields.csv");
+        std::fs::write(
+            &path,
+            "name,address\n\"Smith, Jo\",\"1 High St\nLondon\"\nplain,\"say \"\"hi\"\"\"\n",
+        )
+        .unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let rows: Vec<Vec<String>> = file.csv_records(true).map(Result::unwrap).collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Smith, Jo", "1 High St\nLondon"],
+                vec!["plain", "say \"hi\""],
+            ]
+        );
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let rows: Vec<Vec<String>> = file.csv_records(false).map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], vec!["name", "address"]);
+
+        std::fs::write(&path, "a,b\n1,2,3\n").unwrap();
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let error = file.csv_records(true).next().unwrap().unwrap_err();
+        assert_eq!(error.file_name(), path);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_from_write_only_file() {
+        let path = temp_path("read_from_write_only_file.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+
+        for error in [file.fread().unwrap_err(), file.fread_u8().unwrap_err()] {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(error.message(), "file was not opened with Read");
+            assert_eq!(error.operation(), Operation::Read);
+            assert_eq!(error.options(), file.file_options());
+            assert!(error
+                .to_string()
+                .contains(&format!("({})", file.file_options())));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn write_to_read_only_file() {
+        let mut file = FileOptions::Read.open("README.md").unwrap();
+
+        for error in [
+            file.fwrite("nope".to_string()).unwrap_err(),
+            file.fwrite_u8(b"nope").unwrap_err(),
+        ] {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(error.message(), "file was not opened with Write");
+            assert_eq!(error.operation(), Operation::Write);
+            assert_eq!(error.file_name(), Path::new("README.md"));
+            assert_eq!(
+                error.to_string(),
+                "'README.md' (Read): write failed: file was not opened with Write"
+            );
+        }
+    }
+
+    #[test]
+    fn sync_all_and_sync_data() {
+        let path = temp_path("sync_all_and_sync_data.txt");
+        let mut file = FileOptions::write_new().open(&path).unwrap();
+        file.fwrite("durable".to_string()).unwrap();
+        file.sync_data().unwrap();
+        file.sync_all().unwrap();
+
+        // some platforms refuse to sync a file that is gone; that must be an error, not a panic
+        std::fs::remove_file(&path).unwrap();
+        file.fwrite(" and gone".to_string()).unwrap();
+        for result in [file.sync_data(), file.sync_all()] {
+            if let Err(error) = result {
+                assert_eq!(error.operation(), Operation::Sync);
+                assert_eq!(error.file_name(), path);
+            }
+        }
+    }
+
+    #[test]
+    fn flush_with_durability() {
+        let options = FileOptions::write_new().durability(Durability::Data);
+        assert_eq!(
+            format!("{:?}", options | FileOptions::Read),
+            "FileOptions(Read | Write | Create | Truncate, durability: Data)"
+        );
+
+        let path = temp_path("flush_with_durability.txt");
+        for durability in [Durability::None, Durability::Data, Durability::All] {
+            let mut file = FileOptions::write_new()
+                .durability(durability)
+                .open(&path)
+                .unwrap();
+            file.fwrite("flushed".to_string()).unwrap();
+            file.fflush().unwrap();
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "flushed");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sync_data_then_fresh_handle() {
+        let path = temp_path("sync_data_then_fresh_handle.log");
+        let _ = std::fs::remove_file(&path);
+        let mut log = FileOptions::append_to().open(&path).unwrap();
+        log.writeln("entry one").unwrap();
+        log.sync_data().unwrap();
+        assert_eq!(File::read_to_string(&path).unwrap(), "entry one\n");
+
+        // cheap flushes while writing, and a sync_data once at the end
+        let options = FileOptions::append_to().sync_data_on_close(true);
+        assert_eq!(options.to_string(), "Write|Create|Append|SyncDataOnClose");
+        let mut log = options.open(&path).unwrap();
+        log.writeln("entry two").unwrap();
+        log.fflush().unwrap();
+        log.close().unwrap();
+        assert_eq!(
+            File::read_to_string(&path).unwrap(),
+            "entry one\nentry two\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn line_ending_round_trip_through_text_apis() {
+        let original = "first\r\nsecond has a lone \r inside\r\n\r\nlast";
+        let crlf_path = temp_path("line_ending_round_trip_crlf.txt");
+        let lf_path = temp_path("line_ending_round_trip_lf.txt");
+        std::fs::write(&crlf_path, original).unwrap();
+
+        let mut file = FileOptions::Read.open(&crlf_path).unwrap();
+        let text = file.read_text().unwrap();
+        assert_eq!(text, "first\nsecond has a lone \r inside\n\nlast");
+
+        let mut lf = FileOptions::write_new().open(&lf_path).unwrap();
+        lf.write_text(&text).unwrap();
+        assert_eq!(std::fs::read_to_string(&lf_path).unwrap(), text);
+
+        let mut crlf = FileOptions::write_new()
+            .line_ending(LineEnding::CrLf)
+            .open(&crlf_path)
+            .unwrap();
+        assert_eq!(crlf.line_ending(), LineEnding::CrLf);
+        assert_eq!(crlf.write_text(&text).unwrap(), original.len());
+        assert_eq!(std::fs::read_to_string(&crlf_path).unwrap(), original);
+
+        let mut preserved = FileOptions::Read
+            .line_ending(LineEnding::Preserve)
+            .open(&crlf_path)
+            .unwrap();
+        assert_eq!(preserved.read_text().unwrap(), original);
+
+        // the byte APIs never translate
+        let mut raw = FileOptions::Read.open(&crlf_path).unwrap();
+        assert_eq!(raw.fread_bytes().unwrap(), original.as_bytes());
+        std::fs::remove_file(&crlf_path).unwrap();
+        std::fs::remove_file(&lf_path).unwrap();
+    }
+
+    #[test]
+    fn line_ending_convert() {
+        let text = "a\r\nb\nc\rd";
+        assert_eq!(LineEnding::Lf.convert(text), "a\nb\nc\rd");
+        assert_eq!(LineEnding::CrLf.convert(text), "a\r\nb\r\nc\rd");
+        assert_eq!(LineEnding::Preserve.convert(text), text);
+        assert_eq!(LineEnding::Preserve.as_str(), "\n");
+        if cfg!(windows) {
+            assert_eq!(LineEnding::Native.as_str(), "\r\n");
+        } else {
+            assert_eq!(LineEnding::Native.convert(text), "a\nb\nc\rd");
+        }
+    }
+
+    #[test]
+    fn bytes_written_accumulates() {
+        let path = temp_path("bytes_written_accumulates.txt");
+        let mut file = FileOptions::write_new().read(true).open(&path).unwrap();
+        assert_eq!(file.bytes_written(), 0);
+
+        file.fwrite("first".to_string()).unwrap();
+        file.fwrite_u8(b", second").unwrap();
+        file.append(", third").unwrap();
+        assert_eq!(file.bytes_written(), 20);
+
+        // reads and positional writes don't count
+        file.rewind().unwrap();
+        file.fread().unwrap();
+        file.write_at(0, b"F").unwrap();
+        assert_eq!(file.bytes_written(), 20);
+
+        let reopened = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(reopened.bytes_written(), 0);
+        assert_eq!(file.try_clone().unwrap().bytes_written(), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reader_and_writer_errors_carry_the_path() {
+        let path = temp_path("reader_and_writer_errors_carry_the_path.txt");
+        std::fs::write(&path, [b'o', b'k', 0xff]).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let error = file.fread().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+        assert_eq!(error.options(), FileOptions::Read);
+        assert_eq!(error.operation(), Operation::Read);
+        assert!(error
+            .to_string()
+            .starts_with(&format!("'{}' (Read): read failed: ", path.display())));
+
+        file.rewind().unwrap();
+        assert_eq!(file.read_lines().unwrap_err().file_name(), path);
+        file.rewind().unwrap();
+        assert_eq!(file.read_text().unwrap_err().file_name(), path);
+        file.rewind().unwrap();
+        assert_eq!(file.read_n_bytes(10).unwrap_err().file_name(), path);
+        file.rewind().unwrap();
+        let error = file.lines().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.file_name(), path);
+        assert_eq!(error.options(), FileOptions::Read);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn writer_errors_carry_the_path() {
+        let mut full = FileOptions::Write.open("/dev/full").unwrap();
+        let error = full.fwrite("no space".to_string()).unwrap_err();
+        assert_eq!(error.file_name(), Path::new("/dev/full"));
+        assert_eq!(error.operation(), Operation::Write);
+        assert_eq!(error.kind(), std::io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn read_until_records_of_mixed_lengths() {
+        let path = temp_path("read_until_records_of_mixed_lengths.txt");
+        let long = "x".repeat(20_000);
+        std::fs::write(&path, format!("a;{};b;;{}", long, "y".repeat(255))).unwrap();
+
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        let records: Vec<Vec<u8>> = file.split(b';').map(Result::unwrap).collect();
+        assert_eq!(
+            records,
+            vec![
+                b"a".to_vec(),
+                long.into_bytes(),
+                b"b".to_vec(),
+                Vec::new(),
+                vec![b'y'; 255]
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_write_nothing_to_stdout() {
+        // the harness captures stdout, so run this test again in a child
+        // process with capture off and look between the markers
+        if std::env::var_os("FILE_TEST_STDOUT_CHILD").is_some() {
+            let path = temp_path("reads_write_nothing_to_stdout.txt");
+            std::fs::write(&path, "one\ntwo\n").unwrap();
+            let mut file = FileOptions::Read.open(&path).unwrap();
+            std::io::stdout().write_all(b"<<begin>>").unwrap();
+            file.fread().unwrap();
+            file.rewind().unwrap();
+            file.read_line().unwrap();
+            file.read_n_bytes(2).unwrap();
+            let mut buf = [0; 4];
+            file.rewind().unwrap();
+            file.read_exact(&mut buf).unwrap();
+            file.close().unwrap();
+            std::io::stdout().write_all(b"<<end>>").unwrap();
+            std::fs::remove_file(&path).unwrap();
+            return;
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "tests::reads_write_nothing_to_stdout",
+                "--nocapture",
+            ])
+            .env("FILE_TEST_STDOUT_CHILD", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let start = stdout.find("<<begin>>").unwrap() + "<<begin>>".len();
+        let end = stdout.find("<<end>>").unwrap();
+        assert_eq!(&stdout[start..end], "");
+    }
+
+    #[test]
+    fn buffered_write_to_read_only_file() {
+        let path = temp_path("buffered_write_to_read_only_file.txt");
+        std::fs::write(&path, "orig").unwrap();
+
+        let mut file = FileOptions::Read.open_buffered(&path).unwrap();
+        let error = file.write_all(b"new data").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        let error = FileError::from(error);
+        assert_eq!(error.message(), "file was not opened with Write");
+        assert_eq!(error.file_name(), path);
+        drop(file);
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "orig");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip_overwrites_a_longer_file() {
+        let path = temp_path("gzip_overwrites_a_longer_file.txt.gz");
+        std::fs::write(&path, vec![b'x'; 10_000]).unwrap();
+
+        let mut file = FileOptions::Write.open_gzip(&path).unwrap();
+        file.fwrite("hi".to_string()).unwrap();
+        file.close().unwrap();
+
+        assert!(std::fs::metadata(&path).unwrap().len() < 100);
+        let mut file = FileOptions::Read.open(&path).unwrap();
+        assert_eq!(file.read_gzip().unwrap(), b"hi");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn read_helpers_on_write_only_file() {
+        fn assert_not_readable(error: FileError) {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+            assert_eq!(error.message(), "file was not opened with Read");
+            assert_eq!(error.operation(), Operation::Read);
+        }
+
+        let path = temp_path("read_helpers_on_write_only_file.txt");
+        std::fs::write(&path, "a,b\n").unwrap();
+        let mut file = FileOptions::Write.open(&path).unwrap();
+
+        assert_not_readable(file.read_line().unwrap_err());
+        assert_not_readable(file.split(b',').next().unwrap().unwrap_err());
+        assert_not_readable(file.read_n_bytes(1).unwrap_err());
+        assert_not_readable(file.read_up_to(1).unwrap_err());
+        assert_not_readable(file.read_at(0, 1).unwrap_err());
+        assert_not_readable(file.lines().next().unwrap().unwrap_err());
+        assert_not_readable(file.read_no_bom().unwrap_err());
+        assert_not_readable(file.has_bom().unwrap_err());
+        assert_not_readable(file.read_utf16().unwrap_err());
+        #[cfg(feature = "json")]
+        assert_not_readable(file.read_json::<Vec<u32>>().unwrap_err());
+        #[cfg(feature = "gzip")]
+        assert_not_readable(file.read_gzip().unwrap_err());
+        #[cfg(feature = "csv")]
+        {
+            let mut records = file.csv_records(false);
+            assert_not_readable(records.next().unwrap().unwrap_err());
+            assert!(records.next().is_none());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = fresh_temp_dir("write_atomic_keeps_permissions");
+        let path = dir.join("script.sh");
+        std::fs::write(&path, "old").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        File::write_atomic(&path, b"new").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
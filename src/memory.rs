@@ -0,0 +1,76 @@
+use std::io::{Cursor, Read, Seek, Write};
+
+/// An in-memory stand-in for `File`, for testing code written against the
+/// `Reader`, `Writer` and `Seeker` traits without touching the filesystem.
+///
+/// Like a real file, reads at the end return nothing, writes past the end
+/// extend the contents with zeroes, and in append mode every write goes to the
+/// end regardless of the current position.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFile {
+    cursor: Cursor<Vec<u8>>,
+    append: bool,
+}
+
+impl MemoryFile {
+    /// Create an empty in-memory file.
+    pub fn new() -> MemoryFile {
+        MemoryFile::default()
+    }
+
+    /// Make every write go to the end of the contents, as with `FileOptions::Append`.
+    pub fn append(mut self, append: bool) -> MemoryFile {
+        self.append = append;
+        self
+    }
+
+    /// The contents as they currently stand.
+    pub fn contents(&self) -> &[u8] {
+        self.cursor.get_ref()
+    }
+
+    /// Consume the file and return everything that was written to it.
+    pub fn into_contents(self) -> Vec<u8> {
+        self.cursor.into_inner()
+    }
+}
+
+impl From<Vec<u8>> for MemoryFile {
+    fn from(contents: Vec<u8>) -> MemoryFile {
+        MemoryFile {
+            cursor: Cursor::new(contents),
+            append: false,
+        }
+    }
+}
+
+impl From<&str> for MemoryFile {
+    fn from(contents: &str) -> MemoryFile {
+        MemoryFile::from(contents.as_bytes().to_vec())
+    }
+}
+
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.append {
+            self.cursor.seek(std::io::SeekFrom::End(0))?;
+        }
+        self.cursor.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
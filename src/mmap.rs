@@ -0,0 +1,37 @@
+use memmap2::{Mmap, MmapMut};
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+impl File {
+    /// Map the file into memory read-only, for random access without a read per lookup.
+    ///
+    /// The file must have been opened with `Read`. The map reflects the file
+    /// as it is on disk, not this handle's position, and stays valid after the
+    /// `File` is dropped.
+    ///
+    /// Other processes, or other handles in this one, can still change the
+    /// file underneath the map. Writes show up in the mapped bytes, and
+    /// truncating the file invalidates the pages past its new end, so touching
+    /// them crashes the process with `SIGBUS` rather than returning an error.
+    /// Only map files that nothing else will shrink while the map is alive.
+    pub fn mmap(&self) -> Result<Mmap, FileError> {
+        self.require(FileOptions::Read)?;
+        // SAFETY: the caller is told above not to let the file be truncated while mapped
+        unsafe { Mmap::map(&self.underlying_file) }.map_err(|e| self.error(Operation::Read, e))
+    }
+
+    /// Map the file into memory writably, so changes to the bytes change the file.
+    ///
+    /// The file must have been opened with both `Read` and `Write`, as the OS
+    /// requires for a shared writable map. Changes reach the file eventually on
+    /// their own; call `MmapMut::flush` to wait until they have. The same
+    /// caveats as `mmap` apply: the map must not outlive a truncation of the
+    /// file, and writes to the file through other handles show up in the map.
+    pub fn mmap_mut(&mut self) -> Result<MmapMut, FileError> {
+        self.require(FileOptions::Read)?;
+        self.require(FileOptions::Write)?;
+        // SAFETY: as for `mmap`, the caller is told not to let the file be truncated while mapped
+        unsafe { MmapMut::map_mut(&self.underlying_file) }
+            .map_err(|e| self.error(Operation::Write, e))
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::{Read, Seek};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::defs::{File, FileError, FileOptions, Operation};
+
+const BLOCK_SIZE: usize = 64 * 1024;
+
+impl File {
+    /// The first `n` lines of the file, or all of them if there are fewer, with
+    /// `\n` and `\r\n` endings stripped.
+    ///
+    /// Only as much of the file as those lines need is read, and the position
+    /// is put back where it was afterwards.
+    pub fn head(&mut self, n: usize) -> Result<Vec<String>, FileError> {
+        self.require(FileOptions::Read)?;
+        self.keeping_position(|file| {
+            file.underlying_file
+                .rewind()
+                .map_err(|e| file.error(Operation::Seek, e))?;
+
+            let mut data = Vec::new();
+            let mut newlines = 0;
+            let mut block = vec![0; BLOCK_SIZE];
+            while newlines < n {
+                let read = match file.underlying_file.read(&mut block) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(file.error(Operation::Read, e)),
+                };
+                newlines += block[..read].iter().filter(|&&b| b == b'\n').count();
+                data.extend_from_slice(&block[..read]);
+            }
+
+            // drop whatever was read past the n-th line, which may end partway
+            // through a character, before decoding
+            if newlines >= n {
+                let end = data
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &b)| b == b'\n')
+                    .nth(n.saturating_sub(1))
+                    .map_or(0, |(i, _)| i + 1);
+                data.truncate(end);
+            }
+
+            let mut lines = file.split_lines(&data)?;
+            lines.truncate(n);
+            Ok(lines)
+        })
+    }
+
+    /// The last `n` lines of the file, or all of them if there are fewer, with
+    /// `\n` and `\r\n` endings stripped.
+    ///
+    /// The file is read backwards from the end in blocks, stopping once it has
+    /// the lines it needs, and the position is put back where it was afterwards.
+    pub fn tail(&mut self, n: usize) -> Result<Vec<String>, FileError> {
+        self.require(FileOptions::Read)?;
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+
+        self.keeping_position(|file| {
+            let mut start = file.len()?;
+            // the blocks read so far, last block first
+            let mut blocks: Vec<Vec<u8>> = Vec::new();
+            let mut newlines = 0;
+            let mut trailing_newline = false;
+
+            // a newline ending the last line doesn't separate it from another one
+            while start > 0 && newlines - usize::from(trailing_newline) < n {
+                let size = start.min(BLOCK_SIZE as u64);
+                start -= size;
+
+                let mut block = vec![0; size as usize];
+                let read = file
+                    .underlying_file
+                    .seek(std::io::SeekFrom::Start(start))
+                    .and_then(|_| file.underlying_file.read_exact(&mut block));
+                read.map_err(|e| file.error(Operation::Read, e))?;
+
+                if blocks.is_empty() {
+                    trailing_newline = block.last() == Some(&b'\n');
+                }
+                newlines += block.iter().filter(|&&b| b == b'\n').count();
+                blocks.push(block);
+            }
+
+            let mut data: Vec<u8> = blocks.into_iter().rev().flatten().collect();
+            if start > 0 {
+                // the first line was only partly read, and isn't one of the last `n`
+                let first_newline = data.iter().position(|&b| b == b'\n').unwrap_or(0);
+                data.drain(..=first_newline);
+            }
+
+            let mut lines = file.split_lines(&data)?;
+            Ok(lines.split_off(lines.len().saturating_sub(n)))
+        })
+    }
+
+    /// Follow the file as it grows, like `tail -f`, yielding each complete line
+    /// written from the current position on, with its line ending stripped.
+    ///
+    /// At the end of the file the iterator sleeps for `poll_interval` and checks
+    /// again, so it only ends once stopped through `Follow::stop_handle`. If the
+    /// file shrinks it is read again from the start, and on Unix, if another
+    /// file is moved into its place, as when logs are rotated, that one is opened
+    /// and followed from the start instead.
+    pub fn follow(&mut self, poll_interval: Duration) -> Follow<'_> {
+        Follow {
+            file: self,
+            poll_interval,
+            stop: Arc::new(AtomicBool::new(false)),
+            pending: Vec::new(),
+        }
+    }
+
+    // decode `data` and split it into lines the way `read_lines` does
+    fn split_lines(&self, data: &[u8]) -> Result<Vec<String>, FileError> {
+        match std::str::from_utf8(data) {
+            Ok(text) => Ok(text.lines().map(String::from).collect()),
+            Err(e) => Err(self.error(
+                Operation::Read,
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )),
+        }
+    }
+}
+
+/// An iterator over lines appended to a file, returned by `File::follow`.
+pub struct Follow<'a> {
+    file: &'a mut File,
+    poll_interval: Duration,
+    stop: Arc<AtomicBool>,
+    // bytes read after the last complete line
+    pending: Vec<u8>,
+}
+
+impl Follow<'_> {
+    /// A flag that ends the iteration once set, e.g. from another thread.
+    ///
+    /// The iterator notices within one poll interval, after yielding any
+    /// complete lines it has already read.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, FileError>> {
+        let end = self.pending.iter().position(|&b| b == b'\n')?;
+        let mut line: Vec<u8> = self.pending.drain(..=end).collect();
+        line.pop();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+
+        Some(String::from_utf8(line).map_err(|e| {
+            self.file.error(
+                Operation::Read,
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+            )
+        }))
+    }
+
+    // read until there is a complete line to yield or nothing more has been
+    // written, returning how much was read, so a big backlog streams out line
+    // by line instead of being read into memory all at once
+    fn read_more(&mut self) -> Result<usize, FileError> {
+        let mut block = [0; 8 * 1024];
+        let mut total = 0;
+        loop {
+            match self.file.underlying_file.read(&mut block) {
+                Ok(0) => return Ok(total),
+                Ok(read) => {
+                    self.pending.extend_from_slice(&block[..read]);
+                    total += read;
+                    if block[..read].contains(&b'\n') {
+                        return Ok(total);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.file.error(Operation::Read, e)),
+            }
+        }
+    }
+
+    // start over if the file was truncated or replaced while we were at its end
+    fn check_replaced(&mut self) -> Result<(), FileError> {
+        if self.file.len()? < self.file.stream_position()? {
+            self.pending.clear();
+            self.file.rewind()?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            let current = self.file.underlying_file.metadata();
+            // nothing at the path yet means rotation is still under way
+            if let (Ok(current), Ok(at_path)) = (current, std::fs::metadata(self.file.file_name()))
+            {
+                if (current.dev(), current.ino()) != (at_path.dev(), at_path.ino()) {
+                    let path = self.file.file_name().to_path_buf();
+                    self.pending.clear();
+                    self.file.reopen(&path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for Follow<'_> {
+    type Item = Result<String, FileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.next_line() {
+                return Some(line);
+            }
+            if self.stop.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            match self.read_more() {
+                Ok(0) => {}
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+            if let Err(e) = self.check_replaced() {
+                return Some(Err(e));
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}